@@ -4,8 +4,8 @@ import { Layout } from "./components/Layout";
 import { DashboardHome } from "./pages/DashboardHome";
 import { EntityList } from "./pages/EntityList";
 import { EntityDetail } from "./pages/EntityDetail";
-import type { AppConfig, PendingAction, ConfigStatus } from "./types";
-import { graphqlRequest, MUTATIONS } from "./api";
+import type { AppConfig, PendingAction, ConfigStatus, ScanProgress } from "./types";
+import { graphqlRequest, MUTATIONS, QUERIES } from "./api";
 import { listen } from "@tauri-apps/api/event";
 import { AlertTriangle, RefreshCw, LoaderCircle } from "lucide-react";
 
@@ -50,6 +50,7 @@ function App() {
   const [config, setConfig] = useState<AppConfig | null>(null);
   const [pendingActions, setPendingActions] = useState<PendingAction[]>([]);
   const [configStatus, setConfigStatus] = useState<ConfigStatus | null>(null);
+  const [scanProgress, setScanProgress] = useState<ScanProgress | null>(null);
 
   const fetchData = useCallback(async () => {
     try {
@@ -76,8 +77,17 @@ function App() {
 
   const handleRescan = useCallback(async () => {
     try {
-      await graphqlRequest(MUTATIONS.RESCAN_ISLANDS);
-      alert("Rescan started.");
+      const started = await graphqlRequest(MUTATIONS.RESCAN_ISLANDS);
+      const jobId: string = started.data.rescanIslands;
+
+      // Skeniranje radi u pozadini: pratimo napredak i osvježavamo podatke tek kad završi.
+      for (;;) {
+        const response = await graphqlRequest(QUERIES.GET_SCAN_PROGRESS, { jobId });
+        const progress: ScanProgress | null = response.data?.scanProgress ?? null;
+        setScanProgress(progress);
+        if (!progress || progress.state !== "Running") break;
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
       await fetchData();
     } catch (e) {
       console.error("Rescan failed", e);
@@ -148,6 +158,7 @@ function App() {
                 onResolveAction={handleResolveAction}
                 onMergeAction={handleMergeAction}
                 onRescan={handleRescan}
+                scanProgress={scanProgress}
               />
             }
           />
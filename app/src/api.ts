@@ -62,6 +62,21 @@ export const QUERIES = {
       pendingActions
     }
   `,
+  GET_SCAN_ERRORS: `
+    query {
+      scanErrors
+    }
+  `,
+  GET_SCAN_PROGRESS: `
+    query($jobId: String) {
+      scanProgress(jobId: $jobId)
+    }
+  `,
+  GET_TAGS: `
+    query {
+      tags
+    }
+  `,
   ASK_ORACLE: `
     query($q: String!) {
       askOracle(question: $q)
@@ -76,8 +91,8 @@ export const MUTATIONS = {
     }
   `,
   RESOLVE_ACTION: `
-    mutation($id: String!, $choice: String!) {
-      resolveAction(actionId: $id, choice: $choice)
+    mutation($actionId: String!, $choice: String!, $data: String) {
+      resolveAction(actionId: $actionId, choice: $choice, data: $data)
     }
   `,
   UPDATE_ISLAND_FIELD: `
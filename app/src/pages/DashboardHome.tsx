@@ -1,4 +1,4 @@
-import type { AppConfig, PendingAction } from "../types";
+import type { AppConfig, PendingAction, ScanProgress } from "../types";
 import { ActionCenter } from "../components/ActionCenter";
 import { Activity, Database, Folder } from "lucide-react";
 
@@ -8,6 +8,7 @@ interface DashboardHomeProps {
   onResolveAction: (id: string, choice: "APPROVE" | "REJECT") => Promise<void>;
   onMergeAction: (action: PendingAction, suggestion: string) => Promise<void>;
   onRescan: () => Promise<void>;
+  scanProgress: ScanProgress | null;
 }
 
 export function DashboardHome({
@@ -16,9 +17,12 @@ export function DashboardHome({
   onResolveAction,
   onMergeAction,
   onRescan,
+  scanProgress,
 }: DashboardHomeProps) {
   if (!config) return <div className="p-8 text-white">Loading config...</div>;
 
+  const scanning = scanProgress?.state === "Running";
+
   return (
     <div className="p-8 max-w-7xl mx-auto space-y-8">
       <header className="flex justify-between items-center">
@@ -30,9 +34,13 @@ export function DashboardHome({
         </div>
         <button
           onClick={onRescan}
-          className="bg-slate-800 hover:bg-slate-700 text-white px-4 py-2 rounded-md border border-slate-700 transition-colors flex items-center gap-2"
+          disabled={scanning}
+          className="bg-slate-800 hover:bg-slate-700 disabled:opacity-60 text-white px-4 py-2 rounded-md border border-slate-700 transition-colors flex items-center gap-2"
         >
-          <Activity size={16} /> Rescan System
+          <Activity size={16} className={scanning ? "animate-pulse" : ""} />
+          {scanning
+            ? `Scanning ${scanProgress?.processed}/${scanProgress?.found}`
+            : "Rescan System"}
         </button>
       </header>
 
@@ -36,6 +36,7 @@ export interface IslandDefinition {
   meta_file: string;
   relations: RelationRule[];
   aggregations: AggregationRule[];
+  fields?: CloudField[];
 }
 
 export interface AppConfig {
@@ -56,6 +57,19 @@ export interface PendingAction {
   created_at: string;
 }
 
+export type ScanState = 'Running' | 'Completed' | 'Cancelled' | 'Failed';
+
+export interface ScanProgress {
+  job_id: string;
+  state: ScanState;
+  found: number;
+  processed: number;
+  failed: number;
+  started_at: string;
+  finished_at: string | null;
+  eta_seconds: number | null;
+}
+
 export type ConfigStatus =
   | { type: 'compileTime' }
   | { type: 'compileTimeIgnored' }
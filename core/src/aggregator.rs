@@ -1,25 +1,126 @@
 // core/src/aggregator.rs
 
-use crate::config::{AggregationLogic, AggregationRule};
+use crate::config::{AggregationLogic, AggregationRule, DerivedMetric, DocumentFormat};
+use crate::derived;
+use crate::field_path::FieldPath;
+use crate::filter::FilterExpr;
 use anyhow::Result;
 use glob::glob;
+use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
+/// Rezultat jedne agregacije. Min/Max/First/Last mogu vratiti i tekst (npr. datum).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggregationValue {
+    Number(f64),
+    Text(String),
+    Null,
+}
+
+impl AggregationValue {
+    fn from_yaml(value: &Value) -> Self {
+        match value {
+            Value::String(s) => AggregationValue::Text(s.clone()),
+            Value::Bool(b) => AggregationValue::Text(b.to_string()),
+            _ => as_number(value).map_or(AggregationValue::Null, AggregationValue::Number),
+        }
+    }
+}
+
+impl From<&AggregationValue> for rusqlite::types::Value {
+    fn from(value: &AggregationValue) -> Self {
+        match value {
+            AggregationValue::Number(n) => rusqlite::types::Value::Real(*n),
+            AggregationValue::Text(s) => rusqlite::types::Value::Text(s.clone()),
+            AggregationValue::Null => rusqlite::types::Value::Null,
+        }
+    }
+}
+
+/// Jedna vrijednost pronađena u datoteci, s datumom za First/Last logiku.
+#[derive(Serialize, Deserialize)]
+struct Sample {
+    value: Value,
+    date: Option<String>,
+}
+
+/// (apsolutna putanja datoteke, potpis pravila)
+pub type FileKey = (String, String);
+
+/// Vrijednosti koje je pravilo izvuklo iz jedne datoteke. Dok se `mtime` i
+/// `size` ne promijene datoteka se ne čita; ako se promijene, a `hash`
+/// sadržaja ostane isti, datoteka se ne parsira ponovno.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedFile {
+    pub mtime: i64,
+    pub size: i64,
+    pub hash: String,
+    /// JSON lista uzoraka (prazna ako datoteka ne prolazi filter).
+    pub samples: String,
+}
+
+/// Predmemorija po datoteci za jedan otok. `calculate` bilježi koje je unose
+/// koristio i koje je promijenio, pa se u bazu upisuje samo razlika.
+#[derive(Debug, Default)]
+pub struct FileCache {
+    entries: HashMap<FileKey, CachedFile>,
+    seen: HashSet<FileKey>,
+    changed: HashSet<FileKey>,
+}
+
+impl FileCache {
+    pub fn new(entries: HashMap<FileKey, CachedFile>) -> Self {
+        Self {
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// Novi ili osvježeni unosi.
+    pub fn changed(&self) -> Vec<(&FileKey, &CachedFile)> {
+        self.changed.iter().filter_map(|k| Some((k, self.entries.get(k)?))).collect()
+    }
+
+    /// Unosi za datoteke koje nijedno pravilo više nije pronašlo.
+    pub fn stale(&self) -> Vec<&FileKey> {
+        self.entries.keys().filter(|k| !self.seen.contains(*k)).collect()
+    }
+}
+
 pub struct Aggregator;
 
 impl Aggregator {
+    /// Računa sirove agregacije, a zatim izvedene metrike nad njihovim rezultatima.
+    /// `children` su folderi ugniježđenih otoka; njihove datoteke broje se samo
+    /// za pravila s `include_children`. Nepromijenjene datoteke čitaju se iz `cache`.
     pub fn calculate(
         root_path: &Path,
         rules: &[AggregationRule],
-    ) -> Result<std::collections::HashMap<String, f64>> {
-        let mut results = std::collections::HashMap::new();
+        derived: &[DerivedMetric],
+        children: &[PathBuf],
+        cache: &mut FileCache,
+    ) -> Result<HashMap<String, AggregationValue>> {
+        let mut results = HashMap::new();
 
         for rule in rules {
-            let mut total = 0.0;
-            let mut count = 0.0;
+            let mut samples = Vec::new();
+
+            let filter = rule.filter.as_deref().map(FilterExpr::parse).transpose()?;
+            let target = FieldPath::parse(&rule.target_field)?;
+            let date_path = rule.date_field.as_deref().map(FieldPath::parse).transpose()?;
+            // Pravila s istim izvlačenjem dijele unose u predmemoriji. Oznaka
+            // `file` razlikuje unose sa zbrojem stavki po datoteci od starijih.
+            let signature = format!(
+                "{}|{}|{}|{:?}|file",
+                rule.target_field,
+                rule.filter.as_deref().unwrap_or_default(),
+                rule.date_field.as_deref().unwrap_or_default(),
+                rule.format
+            );
 
             let search_pattern = root_path.join(&rule.path);
             let search_pattern_str = search_pattern.to_string_lossy();
@@ -30,44 +131,291 @@ impl Aggregator {
             );
 
             for path in glob(&search_pattern_str).unwrap_or_else(|_| glob("").unwrap()).flatten() {
-                if path.is_file() {
-                    if let Some(val) = Self::extract_value(&path, &rule.target_field) {
-                        total += val;
-                        count += 1.0;
-                    }
+                if !path.is_file()
+                    || (!rule.include_children && children.iter().any(|c| path.starts_with(c)))
+                {
+                    continue;
                 }
+                samples.extend(Self::file_samples(&path, &signature, cache, |content| {
+                    let Some(doc) = Self::parse_document(&path, content, rule.format) else {
+                        return Vec::new();
+                    };
+                    if filter.as_ref().is_some_and(|f| !f.matches(&doc)) {
+                        return Vec::new();
+                    }
+                    // Datum je zajednički svim vrijednostima iz iste datoteke.
+                    let date = date_path
+                        .as_ref()
+                        .and_then(|p| Self::field_values(&doc, p).into_iter().next())
+                        .map(|d| Self::sort_key(&d));
+                    let values = Self::field_values(&doc, &target);
+                    // Jedna datoteka (npr. ponuda) doprinosi zbroju svojih stavki, pa
+                    // count/average/median/min/max rade nad datotekama, ne stavkama.
+                    let numbers: Vec<f64> = values.iter().filter_map(as_number).collect();
+                    if target.fans_out() && !numbers.is_empty() {
+                        let total = numbers.iter().sum::<f64>();
+                        return vec![Sample {
+                            value: Value::from(total),
+                            date,
+                        }];
+                    }
+                    values
+                        .into_iter()
+                        .map(|value| Sample {
+                            value,
+                            date: date.clone(),
+                        })
+                        .collect()
+                }));
             }
 
-            let final_value = match rule.logic {
-                AggregationLogic::Sum => total,
-                AggregationLogic::Count => count,
-                AggregationLogic::Average => {
-                    if count > 0.0 {
-                        total / count
-                    } else {
-                        0.0
-                    }
-                }
-            };
+            results.insert(rule.name.clone(), Self::reduce(&rule.logic, samples));
+        }
 
-            results.insert(rule.name.clone(), final_value);
+        let known: Vec<&str> = rules.iter().map(|r| r.name.as_str()).collect();
+        for (metric, expr) in derived::evaluation_order(derived, &known)? {
+            let value =
+                expr.eval(&results).map_or(AggregationValue::Null, AggregationValue::Number);
+            results.insert(metric.name.clone(), value);
         }
 
         Ok(results)
     }
 
+    /// Uzorci jedne datoteke: iz predmemorije ako se datoteka nije promijenila,
+    /// inače `extract` nad njenim sadržajem.
+    fn file_samples(
+        path: &Path,
+        signature: &str,
+        cache: &mut FileCache,
+        extract: impl FnOnce(&str) -> Vec<Sample>,
+    ) -> Vec<Sample> {
+        let key = (path.to_string_lossy().to_string(), signature.to_string());
+        cache.seen.insert(key.clone());
+
+        let (mtime, size) = fs::metadata(path)
+            .map(|m| {
+                let mtime = m
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_nanos() as i64);
+                (mtime, m.len() as i64)
+            })
+            .unwrap_or_default();
+        let cached = |entry: &CachedFile| serde_json::from_str::<Vec<Sample>>(&entry.samples).ok();
+
+        if let Some(samples) = cache
+            .entries
+            .get(&key)
+            .filter(|e| e.mtime == mtime && e.size == size)
+            .and_then(cached)
+        {
+            return samples;
+        }
+
+        let Ok(bytes) = fs::read(path) else {
+            return Vec::new();
+        };
+        let hash = content_hash(&bytes);
+        cache.changed.insert(key.clone());
+        if let Some(entry) = cache.entries.get_mut(&key).filter(|e| e.hash == hash) {
+            if let Some(samples) = cached(entry) {
+                entry.mtime = mtime;
+                entry.size = size;
+                return samples;
+            }
+        }
+
+        let samples = std::str::from_utf8(&bytes).map(extract).unwrap_or_default();
+        cache.entries.insert(
+            key,
+            CachedFile {
+                mtime,
+                size,
+                hash,
+                samples: serde_json::to_string(&samples).unwrap_or_else(|_| "[]".to_string()),
+            },
+        );
+        samples
+    }
+
+    /// Svodi skupljene vrijednosti na jedan rezultat prema logici pravila.
+    fn reduce(logic: &AggregationLogic, mut samples: Vec<Sample>) -> AggregationValue {
+        let numbers: Vec<f64> = samples.iter().filter_map(|s| as_number(&s.value)).collect();
+        let total: f64 = numbers.iter().sum();
+
+        match logic {
+            AggregationLogic::Sum => AggregationValue::Number(total),
+            AggregationLogic::Count => AggregationValue::Number(samples.len() as f64),
+            AggregationLogic::Average => {
+                if numbers.is_empty() {
+                    AggregationValue::Number(0.0)
+                } else {
+                    AggregationValue::Number(total / numbers.len() as f64)
+                }
+            }
+            AggregationLogic::Median => {
+                let mut sorted = numbers;
+                sorted.sort_by(f64::total_cmp);
+                let mid = sorted.len() / 2;
+                match sorted.len() {
+                    0 => AggregationValue::Null,
+                    n if n % 2 == 0 => {
+                        AggregationValue::Number((sorted[mid - 1] + sorted[mid]) / 2.0)
+                    }
+                    _ => AggregationValue::Number(sorted[mid]),
+                }
+            }
+            AggregationLogic::Min | AggregationLogic::Max => {
+                let all_numeric = numbers.len() == samples.len();
+                let picked = if all_numeric {
+                    let it = numbers.into_iter();
+                    match logic {
+                        AggregationLogic::Min => it.min_by(f64::total_cmp),
+                        _ => it.max_by(f64::total_cmp),
+                    }
+                    .map(AggregationValue::Number)
+                } else {
+                    // Miješani ili tekstualni podaci (npr. datumi): usporedba kao string.
+                    let it = samples.iter().map(|s| Self::sort_key(&s.value));
+                    match logic {
+                        AggregationLogic::Min => it.min(),
+                        _ => it.max(),
+                    }
+                    .map(AggregationValue::Text)
+                };
+                picked.unwrap_or(AggregationValue::Null)
+            }
+            AggregationLogic::CountDistinct => {
+                let distinct: HashSet<String> =
+                    samples.iter().map(|s| Self::sort_key(&s.value)).collect();
+                AggregationValue::Number(distinct.len() as f64)
+            }
+            AggregationLogic::First | AggregationLogic::Last => {
+                samples.retain(|s| s.date.is_some());
+                let it = samples.into_iter();
+                let picked = match logic {
+                    AggregationLogic::First => it.min_by(|a, b| a.date.cmp(&b.date)),
+                    _ => it.max_by(|a, b| a.date.cmp(&b.date)),
+                };
+                picked.map_or(AggregationValue::Null, |s| {
+                    AggregationValue::from_yaml(&s.value)
+                })
+            }
+        }
+    }
+
+    #[cfg(test)]
     fn extract_value(path: &Path, field: &str) -> Option<f64> {
-        let content = fs::read_to_string(path).ok()?;
-        let yaml: Value = serde_yaml::from_str(&content).ok()?;
-
-        yaml.get(field).and_then(|v| {
-            if let Some(f) = v.as_f64() {
-                Some(f)
-            } else {
-                v.as_i64().map(|i| i as f64)
+        let path_expr = FieldPath::parse(field).ok()?;
+        let values = Self::field_values(&Self::load_document(path, None)?, &path_expr);
+        as_number(values.first()?)
+    }
+
+    /// Učitava datoteku kao YAML stablo. Bez eksplicitnog formata odlučuje ekstenzija.
+    #[cfg(test)]
+    fn load_document(path: &Path, format: Option<DocumentFormat>) -> Option<Value> {
+        Self::parse_document(path, &fs::read_to_string(path).ok()?, format)
+    }
+
+    fn parse_document(path: &Path, content: &str, format: Option<DocumentFormat>) -> Option<Value> {
+        let format = format.unwrap_or_else(|| Self::detect_format(path));
+
+        match format {
+            DocumentFormat::Yaml => serde_yaml::from_str(content).ok(),
+            DocumentFormat::Markdown => serde_yaml::from_str(Self::front_matter(content)?).ok(),
+            DocumentFormat::Json => {
+                let json: serde_json::Value = serde_json::from_str(content).ok()?;
+                serde_yaml::to_value(json).ok()
             }
-        })
+            DocumentFormat::Toml => {
+                let table: toml::Table = content.parse().ok()?;
+                Some(Self::toml_to_yaml(toml::Value::Table(table)))
+            }
+        }
     }
+
+    fn detect_format(path: &Path) -> DocumentFormat {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+            Some("md" | "markdown") => DocumentFormat::Markdown,
+            Some("json") => DocumentFormat::Json,
+            Some("toml") => DocumentFormat::Toml,
+            _ => DocumentFormat::Yaml,
+        }
+    }
+
+    /// Izdvaja YAML blok između početnog `---` i zatvarajućeg `---` (ili `...`).
+    fn front_matter(content: &str) -> Option<&str> {
+        let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+        let rest = content.strip_prefix("---")?;
+        let rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n'))?;
+
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            let trimmed = line.trim_end();
+            if trimmed == "---" || trimmed == "..." {
+                return Some(&rest[..offset]);
+            }
+            offset += line.len();
+        }
+        None
+    }
+
+    fn toml_to_yaml(value: toml::Value) -> Value {
+        match value {
+            toml::Value::String(s) => Value::String(s),
+            toml::Value::Integer(i) => Value::from(i),
+            toml::Value::Float(f) => Value::from(f),
+            toml::Value::Boolean(b) => Value::Bool(b),
+            // Datumi postaju ISO stringovi, kao i u YAML-u.
+            toml::Value::Datetime(d) => Value::String(d.to_string()),
+            toml::Value::Array(items) => {
+                Value::Sequence(items.into_iter().map(Self::toml_to_yaml).collect())
+            }
+            toml::Value::Table(table) => Value::Mapping(
+                table
+                    .into_iter()
+                    .map(|(k, v)| (Value::String(k), Self::toml_to_yaml(v)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Dohvaća skalarne vrijednosti na putanji; `null`, liste i mape se ignoriraju.
+    fn field_values(yaml: &Value, path: &FieldPath) -> Vec<Value> {
+        path.resolve(yaml)
+            .into_iter()
+            .filter(|v| {
+                !matches!(
+                    v,
+                    Value::Null | Value::Sequence(_) | Value::Mapping(_) | Value::Tagged(_)
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn sort_key(value: &Value) -> String {
+        match value {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+fn as_number(v: &Value) -> Option<f64> {
+    v.as_f64().or_else(|| v.as_i64().map(|i| i as f64))
+}
+
+/// FNV-1a (64 bit): stabilan između verzija, dovoljan za otkrivanje promjene sadržaja.
+fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
 }
 
 // ============== UNIT TESTS ==============
@@ -101,4 +449,208 @@ mod tests {
         fs::write(&file_path, "amount: 'hello'").unwrap();
         assert_eq!(Aggregator::extract_value(&file_path, "amount"), None);
     }
+
+    #[test]
+    fn test_calculate_applies_filter() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.yaml"), "status: unpaid\namount: 1500").unwrap();
+        fs::write(dir.path().join("b.yaml"), "status: paid\namount: 2000").unwrap();
+        fs::write(dir.path().join("c.yaml"), "status: unpaid\namount: 300").unwrap();
+
+        let rule = AggregationRule {
+            name: "unpaid_total".to_string(),
+            path: "*.yaml".to_string(),
+            target_field: "amount".to_string(),
+            logic: AggregationLogic::Sum,
+            filter: Some("status == 'unpaid'".to_string()),
+            date_field: None,
+            format: None,
+            include_children: false,
+        };
+
+        let results =
+            Aggregator::calculate(dir.path(), &[rule], &[], &[], &mut FileCache::default())
+                .unwrap();
+        assert_eq!(
+            results.get("unpaid_total"),
+            Some(&AggregationValue::Number(1800.0))
+        );
+    }
+
+    #[test]
+    fn test_calculate_extended_logics() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("a.yaml"),
+            "client: Acme\namount: 100\ndate: '2024-01-10'",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("b.yaml"),
+            "client: Beta\namount: 400\ndate: '2024-03-05'",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("c.yaml"),
+            "client: Acme\namount: 250\ndate: '2024-02-01'",
+        )
+        .unwrap();
+
+        let rule = |name: &str, field: &str, logic: AggregationLogic| AggregationRule {
+            name: name.to_string(),
+            path: "*.yaml".to_string(),
+            target_field: field.to_string(),
+            logic,
+            filter: None,
+            format: None,
+            date_field: Some("date".to_string()),
+            include_children: false,
+        };
+        let rules = vec![
+            rule("min", "amount", AggregationLogic::Min),
+            rule("max", "amount", AggregationLogic::Max),
+            rule("median", "amount", AggregationLogic::Median),
+            rule("clients", "client", AggregationLogic::CountDistinct),
+            rule("first_amount", "amount", AggregationLogic::First),
+            rule("last_client", "client", AggregationLogic::Last),
+            rule("latest_date", "date", AggregationLogic::Max),
+        ];
+
+        let results =
+            Aggregator::calculate(dir.path(), &rules, &[], &[], &mut FileCache::default()).unwrap();
+        assert_eq!(results["min"], AggregationValue::Number(100.0));
+        assert_eq!(results["max"], AggregationValue::Number(400.0));
+        assert_eq!(results["median"], AggregationValue::Number(250.0));
+        assert_eq!(results["clients"], AggregationValue::Number(2.0));
+        assert_eq!(results["first_amount"], AggregationValue::Number(100.0));
+        assert_eq!(
+            results["last_client"],
+            AggregationValue::Text("Beta".to_string())
+        );
+        assert_eq!(
+            results["latest_date"],
+            AggregationValue::Text("2024-03-05".to_string())
+        );
+    }
+
+    #[test]
+    fn test_calculate_fans_out_over_nested_items() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("offer_1.yaml"),
+            "items:\n  - total: 100\n  - total: 250.5\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("offer_2.yaml"), "items:\n  - total: 49.5\n").unwrap();
+
+        let rule = AggregationRule {
+            name: "offered".to_string(),
+            path: "*.yaml".to_string(),
+            target_field: "items[*].total".to_string(),
+            logic: AggregationLogic::Sum,
+            filter: None,
+            format: None,
+            date_field: None,
+            include_children: false,
+        };
+
+        let results = Aggregator::calculate(
+            dir.path(),
+            std::slice::from_ref(&rule),
+            &[],
+            &[],
+            &mut FileCache::default(),
+        )
+        .unwrap();
+        assert_eq!(results["offered"], AggregationValue::Number(400.0));
+
+        // Ostale logike rade nad zbrojem po datoteci (350.5 i 49.5), ne nad stavkama.
+        let with_logic = |name: &str, logic| AggregationRule {
+            name: name.to_string(),
+            logic,
+            ..rule.clone()
+        };
+        let rules = [
+            with_logic("offers", AggregationLogic::Count),
+            with_logic("average", AggregationLogic::Average),
+            with_logic("largest", AggregationLogic::Max),
+        ];
+        let results =
+            Aggregator::calculate(dir.path(), &rules, &[], &[], &mut FileCache::default()).unwrap();
+        assert_eq!(results["offers"], AggregationValue::Number(2.0));
+        assert_eq!(results["average"], AggregationValue::Number(200.0));
+        assert_eq!(results["largest"], AggregationValue::Number(350.5));
+    }
+
+    #[test]
+    fn test_file_cache_rereads_only_changed_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.yaml"), "amount: 10").unwrap();
+        fs::write(dir.path().join("b.yaml"), "amount: 20").unwrap();
+        let rule = |name: &str, logic| AggregationRule {
+            name: name.to_string(),
+            path: "*.yaml".to_string(),
+            target_field: "amount".to_string(),
+            logic,
+            filter: None,
+            format: None,
+            date_field: None,
+            include_children: false,
+        };
+        let rules = [
+            rule("total", AggregationLogic::Sum),
+            rule("count", AggregationLogic::Count),
+        ];
+        let run = |entries: HashMap<FileKey, CachedFile>| {
+            let mut cache = FileCache::new(entries);
+            let results = Aggregator::calculate(dir.path(), &rules, &[], &[], &mut cache).unwrap();
+            (results, cache)
+        };
+
+        // Oba pravila dijele isti potpis, pa je unos jedan po datoteci.
+        let (results, cache) = run(HashMap::new());
+        assert_eq!(results["total"], AggregationValue::Number(30.0));
+        assert_eq!(cache.changed().len(), 2);
+
+        let (results, cache) = run(cache.entries);
+        assert_eq!(results["total"], AggregationValue::Number(30.0));
+        assert!(cache.changed().is_empty());
+
+        fs::write(dir.path().join("b.yaml"), "amount: 200").unwrap();
+        fs::remove_file(dir.path().join("a.yaml")).unwrap();
+        let (results, cache) = run(cache.entries);
+        assert_eq!(results["total"], AggregationValue::Number(200.0));
+        assert_eq!(results["count"], AggregationValue::Number(1.0));
+        assert_eq!(cache.changed().len(), 1);
+        assert_eq!(cache.stale().len(), 1);
+    }
+
+    #[test]
+    fn test_load_document_formats() {
+        let dir = tempdir().unwrap();
+        let md = dir.path().join("task.md");
+        let json = dir.path().join("invoice.json");
+        let toml = dir.path().join("offer.toml");
+        let txt = dir.path().join("notes.txt");
+
+        fs::write(&md, "---\nhours: 6\n---\n# Task\n\nhours: 99\n").unwrap();
+        fs::write(&json, r#"{"amount": 120.5}"#).unwrap();
+        fs::write(&toml, "amount = 42\ndate = 2024-01-01\n").unwrap();
+        fs::write(&txt, "---\namount: 7\n---\nbody").unwrap();
+
+        assert_eq!(Aggregator::extract_value(&md, "hours"), Some(6.0));
+        assert_eq!(Aggregator::extract_value(&json, "amount"), Some(120.5));
+        assert_eq!(Aggregator::extract_value(&toml, "amount"), Some(42.0));
+
+        let doc = Aggregator::load_document(&toml, None).unwrap();
+        assert_eq!(
+            doc.get("date"),
+            Some(&Value::String("2024-01-01".to_string()))
+        );
+
+        // Bez overridea .txt se čita kao YAML i ne uspijeva; s overrideom čita front-matter.
+        assert!(Aggregator::load_document(&txt, None).is_none());
+        let doc = Aggregator::load_document(&txt, Some(DocumentFormat::Markdown)).unwrap();
+        assert_eq!(doc.get("amount"), Some(&Value::from(7)));
+    }
 }
@@ -1,14 +1,17 @@
 // core/src/api.rs
 
-use crate::cloud::SqliteManager;
+use crate::cloud::{DataFilter, DataQuery, FilterOp, SortKey, SqliteManager};
 use crate::config::{
     env::{ConfigStatus, EnvConfig},
     Config,
 };
+use crate::events::{EventBus, ValterEvent};
 use crate::fs_writer::FsWriter;
 use crate::processor::EventProcessor;
-use async_graphql::{Context, EmptySubscription, Json, Object, Schema};
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use crate::scan::{ScanJobs, ScanProgress};
+use crate::typed_schema;
+use async_graphql::{Context, InputObject, Json, Object, Schema, SimpleObject, Subscription};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
 use axum::{
     extract::Extension,
     http::{header, Method, StatusCode, Uri},
@@ -16,13 +19,16 @@ use axum::{
     routing::get,
     Router,
 };
+use rusqlite::types::Value as SqlValue;
 use rust_embed::RustEmbed;
+use serde::Deserialize;
 use serde_json::Value;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::net::TcpListener;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{error, info};
 
 // Nema više `cfg` magije. Samo jednostavna definicija.
 // `build.rs` sada osigurava da `../app/dist` uvijek postoji.
@@ -34,9 +40,95 @@ pub struct ApiState {
     pub cloud: Arc<SqliteManager>,
     pub config: Arc<Config>,
     pub processor: Arc<EventProcessor>,
+    pub scans: Arc<ScanJobs>,
     pub env_config: Arc<EnvConfig>,
 }
 
+/// Filter nad jednim poljem. Može se postaviti više operatora odjednom (npr. `gte` + `lte` za raspon).
+#[derive(InputObject, Deserialize)]
+pub struct FieldFilterInput {
+    pub field: String,
+    pub eq: Option<Json<Value>>,
+    pub ne: Option<Json<Value>>,
+    pub contains: Option<String>,
+    pub gt: Option<Json<Value>>,
+    pub gte: Option<Json<Value>>,
+    pub lt: Option<Json<Value>>,
+    pub lte: Option<Json<Value>>,
+}
+
+#[derive(InputObject, Deserialize)]
+pub struct SortInput {
+    pub field: String,
+    #[graphql(default)]
+    #[serde(default)]
+    pub desc: bool,
+}
+
+fn json_to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        Value::Number(n) => n.as_i64().map_or_else(
+            || SqlValue::Real(n.as_f64().unwrap_or_default()),
+            SqlValue::Integer,
+        ),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn tag_filters(tags: Option<Vec<String>>) -> impl Iterator<Item = DataFilter> {
+    tags.unwrap_or_default().into_iter().map(|tag| DataFilter {
+        field: "tags".to_string(),
+        op: FilterOp::HasTag,
+        value: SqlValue::Text(tag),
+    })
+}
+
+pub(crate) fn data_query(
+    filter: Option<Vec<FieldFilterInput>>,
+    sort: Option<Vec<SortInput>>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> DataQuery {
+    let mut filters = Vec::new();
+    for f in filter.unwrap_or_default() {
+        let ops = [
+            (FilterOp::Eq, f.eq.map(|v| json_to_sql(&v))),
+            (FilterOp::Ne, f.ne.map(|v| json_to_sql(&v))),
+            (FilterOp::Contains, f.contains.map(SqlValue::Text)),
+            (FilterOp::Gt, f.gt.map(|v| json_to_sql(&v))),
+            (FilterOp::Gte, f.gte.map(|v| json_to_sql(&v))),
+            (FilterOp::Lt, f.lt.map(|v| json_to_sql(&v))),
+            (FilterOp::Lte, f.lte.map(|v| json_to_sql(&v))),
+        ];
+        for (op, value) in ops {
+            if let Some(value) = value {
+                filters.push(DataFilter {
+                    field: f.field.clone(),
+                    op,
+                    value,
+                });
+            }
+        }
+    }
+
+    DataQuery {
+        filters,
+        sort: sort
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| SortKey {
+                field: s.field,
+                descending: s.desc,
+            })
+            .collect(),
+        limit,
+        offset,
+    }
+}
+
 pub struct QueryRoot;
 
 #[Object]
@@ -53,14 +145,134 @@ impl QueryRoot {
         Json(state.env_config.status.clone())
     }
 
-    async fn cloud_data(&self, ctx: &Context<'_>, name: String) -> Json<Vec<Value>> {
+    /// Retci Cloud tablice. Svi argumenti su opcionalni; filteri se spajaju s AND.
+    /// `includeReferrers` dodaje svakom zapisu `_referrers`: otoke koji ga referenciraju,
+    /// grupirane po tipu otoka.
+    #[allow(clippy::too_many_arguments)]
+    async fn cloud_data(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        filter: Option<Vec<FieldFilterInput>>,
+        sort: Option<Vec<SortInput>>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        #[graphql(default = false)] include_referrers: bool,
+    ) -> async_graphql::Result<Json<Vec<Value>>> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        let query = data_query(filter, sort, limit, offset);
+        let mut rows = state.cloud.fetch_dynamic(&name, &query)?;
+        if include_referrers {
+            for row in &mut rows {
+                let Some(id) = row["id"].as_str().map(str::to_string) else {
+                    continue;
+                };
+                let referrers = state.cloud.fetch_referrers(&state.config, &name, &id)?;
+                row["_referrers"] = Value::Object(referrers);
+            }
+        }
+        Ok(Json(rows))
+    }
+
+    /// Relacije `many` dolaze kao liste UUID-ova, a svaki otok nosi i `tags`.
+    /// `expandRelations` zamjenjuje UUID-ove relacija punim Cloud zapisima.
+    /// `tags` vraća samo otoke koji imaju sve navedene tagove.
+    #[allow(clippy::too_many_arguments)]
+    async fn island_data(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        filter: Option<Vec<FieldFilterInput>>,
+        sort: Option<Vec<SortInput>>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        tags: Option<Vec<String>>,
+        #[graphql(default = false)] expand_relations: bool,
+    ) -> async_graphql::Result<Json<Vec<Value>>> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        let mut query = data_query(filter, sort, limit, offset);
+        query.filters.extend(tag_filters(tags));
+        let mut rows = state.cloud.fetch_dynamic(&name, &query)?;
+        state.cloud.attach_island_links(&state.config, &name, &mut rows)?;
+        state.cloud.attach_island_tags(&name, &mut rows)?;
+        if expand_relations {
+            state.cloud.expand_relations(&state.config, &name, &mut rows)?;
+        }
+        Ok(Json(rows))
+    }
+
+    /// Otoci kao stablo pod-projekata (`children`, `_type`). `islandType`
+    /// ograničava korijene na jedan tip otoka.
+    async fn island_tree(
+        &self,
+        ctx: &Context<'_>,
+        island_type: Option<String>,
+    ) -> async_graphql::Result<Json<Vec<Value>>> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        Ok(Json(state.cloud.fetch_island_tree(
+            &state.config,
+            island_type.as_deref(),
+        )?))
+    }
+
+    /// Ukupan broj redaka (za paginaciju) uz iste filtere kao `cloudData`.
+    async fn cloud_data_count(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        filter: Option<Vec<FieldFilterInput>>,
+    ) -> async_graphql::Result<i64> {
         let state = ctx.data::<ApiState>().expect("ApiState missing");
-        state.cloud.fetch_all_dynamic(&name).map(Json).unwrap_or(Json(vec![]))
+        let query = data_query(filter, None, None, None);
+        Ok(state.cloud.count_dynamic(&name, &query.filters)?)
     }
 
-    async fn island_data(&self, ctx: &Context<'_>, name: String) -> Json<Vec<Value>> {
+    async fn island_data_count(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        filter: Option<Vec<FieldFilterInput>>,
+        tags: Option<Vec<String>>,
+    ) -> async_graphql::Result<i64> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        let mut query = data_query(filter, None, None, None);
+        query.filters.extend(tag_filters(tags));
+        Ok(state.cloud.count_dynamic(&name, &query.filters)?)
+    }
+
+    /// Svi tagovi s brojem otoka (`[{name, count}]`), najčešći prvi.
+    async fn tags(
+        &self,
+        ctx: &Context<'_>,
+        island_type: Option<String>,
+    ) -> async_graphql::Result<Json<Vec<Value>>> {
         let state = ctx.data::<ApiState>().expect("ApiState missing");
-        state.cloud.fetch_all_dynamic(&name).map(Json).unwrap_or(Json(vec![]))
+        Ok(Json(state.cloud.fetch_tag_counts(island_type.as_deref())?))
+    }
+
+    /// Meta fajlovi koji se ne daju obraditi (npr. neispravan YAML), s linijom i
+    /// stupcem greške. Fajl nestaje s popisa kad se ispravi ili obriše.
+    async fn scan_errors(
+        &self,
+        ctx: &Context<'_>,
+        island_type: Option<String>,
+    ) -> async_graphql::Result<Json<Vec<Value>>> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        Ok(Json(state.cloud.fetch_scan_errors(island_type.as_deref())?))
+    }
+
+    /// Napredak skeniranja; bez `jobId` vraća zadnji pokrenuti posao.
+    async fn scan_progress(
+        &self,
+        ctx: &Context<'_>,
+        job_id: Option<String>,
+    ) -> Option<Json<ScanProgress>> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        let job = match job_id {
+            Some(id) => state.scans.get(&id),
+            None => state.scans.latest(),
+        };
+        job.map(|j| Json(j.progress()))
     }
 
     async fn pending_actions(&self, ctx: &Context<'_>) -> Json<Vec<Value>> {
@@ -117,11 +329,24 @@ pub struct MutationRoot;
 
 #[Object]
 impl MutationRoot {
+    /// Pokreće skeniranje u pozadini i vraća ID posla (vidi `scanProgress`).
+    /// Ako skeniranje već traje, vraća njegov ID.
     async fn rescan_islands(&self, ctx: &Context<'_>) -> String {
         let state = ctx.data::<ApiState>().expect("ApiState missing");
         info!("Manual Rescan Requested via API");
-        state.processor.scan_on_startup();
-        "Rescan Complete".to_string()
+        state.scans.start(state.processor.clone()).id().to_string()
+    }
+
+    /// Otkazuje skeniranje. Vraća `false` ako posao ne postoji ili je već gotov.
+    async fn cancel_scan(&self, ctx: &Context<'_>, job_id: String) -> bool {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        match state.scans.get(&job_id) {
+            Some(job) if !job.is_finished() => {
+                job.cancel();
+                true
+            }
+            _ => false,
+        }
     }
 
     async fn update_island_field(
@@ -161,32 +386,203 @@ impl MutationRoot {
             let parsed: std::collections::HashMap<String, String> =
                 serde_json::from_str(&initial_data).unwrap_or_default();
             let data: Vec<_> = parsed.into_iter().collect();
-            let root = def.root_path.replace("/*", "");
-            if FsWriter::create_island(&root, &name, data).is_ok() {
+            // Novi otok se može stvoriti samo kad je root_path oblika `<folder>/*`.
+            let Some(root) = def.root_pattern().ok().and_then(|p| p.creation_dir()) else {
+                return "Error".to_string();
+            };
+            if FsWriter::create_island(&root.to_string_lossy(), &name, data).is_ok() {
                 return "Created".to_string();
             }
         }
         "Error".to_string()
     }
 
-    async fn resolve_action(&self, ctx: &Context<'_>, action_id: String, choice: String) -> String {
+    /// Kreira Cloud entitet iz JSON objekta (`data`). Vraća UUID novog zapisa.
+    async fn create_cloud_entity(
+        &self,
+        ctx: &Context<'_>,
+        cloud: String,
+        data: String,
+    ) -> async_graphql::Result<String> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        let data: serde_json::Map<String, Value> = serde_json::from_str(&data)?;
+        Ok(state.cloud.create_cloud_entity(&state.config, &cloud, &data)?)
+    }
+
+    /// Ažurira samo polja poslana u `data`.
+    async fn update_cloud_entity(
+        &self,
+        ctx: &Context<'_>,
+        cloud: String,
+        id: String,
+        data: String,
+    ) -> async_graphql::Result<String> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        let data: serde_json::Map<String, Value> = serde_json::from_str(&data)?;
+        state.cloud.update_cloud_entity(&state.config, &cloud, &id, &data)?;
+        Ok("Updated".to_string())
+    }
+
+    /// Briše entitet; odbija ako ga Island relacije još koriste.
+    async fn delete_cloud_entity(
+        &self,
+        ctx: &Context<'_>,
+        cloud: String,
+        id: String,
+    ) -> async_graphql::Result<String> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        state.cloud.delete_cloud_entity(&state.config, &cloud, &id)?;
+        Ok("Deleted".to_string())
+    }
+
+    /// `data` je opcionalni JSON s dodatnim poljima za novi entitet (kod APPROVE).
+    /// Greške validacije se vraćaju kroz GraphQL `errors`.
+    async fn resolve_action(
+        &self,
+        ctx: &Context<'_>,
+        action_id: String,
+        choice: String,
+        data: Option<String>,
+    ) -> async_graphql::Result<String> {
         let state = ctx.data::<ApiState>().expect("ApiState missing");
         match choice.as_str() {
-            "APPROVE" => state
-                .cloud
-                .approve_pending_creation(&action_id)
-                .map(|id| format!("Created: {}", id))
-                .unwrap_or("Error".to_string()),
+            "APPROVE" => {
+                let extra: serde_json::Map<String, Value> = match data {
+                    Some(raw) => serde_json::from_str(&raw)?,
+                    None => serde_json::Map::new(),
+                };
+                let id = state.cloud.approve_pending_creation(&action_id, &state.config, &extra)?;
+                Ok(format!("Created: {}", id))
+            }
             "REJECT" => {
-                let _ = state.cloud.reject_pending_action(&action_id);
-                "Rejected".to_string()
+                state.cloud.reject_pending_action(&action_id)?;
+                Ok("Rejected".to_string())
             }
-            _ => "Unknown".to_string(),
+            _ => Ok("Unknown".to_string()),
         }
     }
 }
 
-pub type ValterSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+#[derive(SimpleObject, Clone)]
+pub struct IslandEvent {
+    pub island_type: String,
+    pub id: String,
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct IslandRemovedEvent {
+    pub island_type: String,
+    pub id: String,
+    /// `true` ako je redak zadržan sa `status = 'Missing'`.
+    pub archived: bool,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct PendingActionEvent {
+    pub action_id: String,
+    /// `Pending` za novu akciju, `Resolved` ili `Rejected` nakon odluke.
+    pub status: String,
+    pub target_table: Option<String>,
+    pub value: Option<String>,
+}
+
+/// Pretvara sabirnicu u stream i propušta samo događaje koje `select` prepozna.
+/// Zaostali (lagged) događaji se preskaču umjesto da prekinu pretplatu.
+fn event_stream<T>(
+    events: &EventBus,
+    select: impl Fn(ValterEvent) -> Option<T> + Send + 'static,
+) -> impl Stream<Item = T> {
+    BroadcastStream::new(events.subscribe()).filter_map(move |e| e.ok().and_then(&select))
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Island je (ponovno) upisan nakon skeniranja. `islandType` sužava na jedan tip.
+    async fn island_updated(
+        &self,
+        ctx: &Context<'_>,
+        island_type: Option<String>,
+    ) -> impl Stream<Item = IslandEvent> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        event_stream(state.cloud.events(), move |e| match e {
+            ValterEvent::IslandUpserted {
+                island_type: t,
+                id,
+                name,
+                path,
+            } if island_type.as_ref().is_none_or(|wanted| wanted == &t) => Some(IslandEvent {
+                island_type: t,
+                id,
+                name,
+                path,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Folder ili meta fajl otoka je nestao s diska.
+    async fn island_removed(
+        &self,
+        ctx: &Context<'_>,
+        island_type: Option<String>,
+    ) -> impl Stream<Item = IslandRemovedEvent> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        event_stream(state.cloud.events(), move |e| match e {
+            ValterEvent::IslandRemoved {
+                island_type: t,
+                id,
+                archived,
+            } if island_type.as_ref().is_none_or(|wanted| wanted == &t) => {
+                Some(IslandRemovedEvent {
+                    island_type: t,
+                    id,
+                    archived,
+                })
+            }
+            _ => None,
+        })
+    }
+
+    async fn pending_action_changed(
+        &self,
+        ctx: &Context<'_>,
+    ) -> impl Stream<Item = PendingActionEvent> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        event_stream(state.cloud.events(), |e| match e {
+            ValterEvent::PendingActionCreated {
+                action_id,
+                target_table,
+                value,
+            } => Some(PendingActionEvent {
+                action_id,
+                status: "Pending".to_string(),
+                target_table: Some(target_table),
+                value: Some(value),
+            }),
+            ValterEvent::PendingActionResolved { action_id, status } => Some(PendingActionEvent {
+                action_id,
+                status,
+                target_table: None,
+                value: None,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Emitira `true` kad se konfiguracija promijeni i API krene u reload.
+    async fn config_reloaded(&self, ctx: &Context<'_>) -> impl Stream<Item = bool> {
+        let state = ctx.data::<ApiState>().expect("ApiState missing");
+        event_stream(state.cloud.events(), |e| {
+            matches!(e, ValterEvent::ConfigReloaded).then_some(true)
+        })
+    }
+}
+
+pub type ValterSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 async fn graphql_handler(schema: Extension<ValterSchema>, req: GraphQLRequest) -> GraphQLResponse {
     schema.execute(req.into_inner()).await.into()
@@ -194,7 +590,20 @@ async fn graphql_handler(schema: Extension<ValterSchema>, req: GraphQLRequest) -
 
 async fn graphiql() -> impl IntoResponse {
     Html(async_graphql::http::playground_source(
-        async_graphql::http::GraphQLPlaygroundConfig::new("/graphql"),
+        async_graphql::http::GraphQLPlaygroundConfig::new("/graphql").subscription_endpoint("/ws"),
+    ))
+}
+
+async fn typed_graphql_handler(
+    schema: Extension<async_graphql::dynamic::Schema>,
+    req: GraphQLRequest,
+) -> GraphQLResponse {
+    schema.execute(req.into_inner()).await.into()
+}
+
+async fn typed_graphiql() -> impl IntoResponse {
+    Html(async_graphql::http::playground_source(
+        async_graphql::http::GraphQLPlaygroundConfig::new("/graphql/typed"),
     ))
 }
 
@@ -225,14 +634,16 @@ pub async fn start_server(
     cloud: Arc<SqliteManager>,
     config: Arc<Config>,
     processor: Arc<EventProcessor>,
+    scans: Arc<ScanJobs>,
     env_config: Arc<EnvConfig>,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
 ) -> anyhow::Result<()> {
-    let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+    let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
         .data(ApiState {
-            cloud,
+            cloud: cloud.clone(),
             config: config.clone(),
             processor,
+            scans,
             env_config,
         })
         .finish();
@@ -242,11 +653,23 @@ pub async fn start_server(
         .allow_methods(vec![Method::GET, Method::POST, Method::OPTIONS])
         .allow_headers([header::CONTENT_TYPE, header::ACCEPT]);
 
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/graphql", get(graphiql).post(graphql_handler))
-        .fallback(static_handler)
-        .layer(Extension(schema))
-        .layer(cors);
+        .route_service("/ws", GraphQLSubscription::new(schema.clone()));
+
+    // Tipizirana shema se gradi iz trenutne konfiguracije; ako ne uspije,
+    // netipizirani `/graphql` i dalje radi.
+    match typed_schema::build(&config, cloud) {
+        Ok(typed) => {
+            app = app.route(
+                "/graphql/typed",
+                get(typed_graphiql).post(typed_graphql_handler).layer(Extension(typed)),
+            );
+        }
+        Err(e) => error!("Failed to build typed GraphQL schema: {}", e),
+    }
+
+    let app = app.fallback(static_handler).layer(Extension(schema)).layer(cors);
 
     let port = config.global.port;
     let addr = format!("0.0.0.0:{}", port);
@@ -1,6 +1,9 @@
-use crate::config::Config;
+use crate::aggregator::{AggregationValue, CachedFile, FileCache};
+use crate::config::{Cardinality, CloudDefinition, Config, FieldType, EXTRA_COLUMN};
+use crate::events::{EventBus, ValterEvent};
+use crate::fields;
 use anyhow::{Context, Result};
-use rusqlite::{params, types::Value as SqlValue, Connection};
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection, OptionalExtension};
 use serde_json::{Map, Value as JsonValue};
 use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
@@ -8,9 +11,89 @@ use strsim::levenshtein;
 use tracing::{info, warn};
 use uuid::Uuid;
 
+/// Briše tagove koje više nijedan otok ne koristi.
+const DELETE_UNUSED_TAGS: &str =
+    "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM island_tags)";
+
+/// Sigurno citira SQL identifikator (ime tablice/stupca): `O"Brien` -> `"O""Brien"`.
+/// Vrijednosti se nikad ne ugrađuju u upit, nego se uvijek vežu kao parametri.
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Contains,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// Otok ima tag `value` (bez obzira na velika/mala slova); `field` se ignorira.
+    HasTag,
+}
+
+/// Jedan uvjet nad stupcem, npr. `amount >= 100`.
+#[derive(Debug, Clone)]
+pub struct DataFilter {
+    pub field: String,
+    pub op: FilterOp,
+    pub value: SqlValue,
+}
+
+#[derive(Debug, Clone)]
+pub struct SortKey {
+    pub field: String,
+    pub descending: bool,
+}
+
+/// Parametri za `fetch_dynamic`: filteri (spojeni s AND), sortiranje i paginacija.
+#[derive(Debug, Clone, Default)]
+pub struct DataQuery {
+    pub filters: Vec<DataFilter>,
+    pub sort: Vec<SortKey>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// Vrsta greške pri obradi meta fajla.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanErrorKind {
+    Io,
+    Yaml,
+    Database,
+    Validation,
+    Processing,
+}
+
+impl ScanErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanErrorKind::Io => "io",
+            ScanErrorKind::Yaml => "yaml",
+            ScanErrorKind::Database => "database",
+            ScanErrorKind::Validation => "validation",
+            ScanErrorKind::Processing => "processing",
+        }
+    }
+}
+
+/// Meta fajl koji se nije dao obraditi. `line`/`column` postoje za YAML greške.
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub path: String,
+    pub island_type: String,
+    pub kind: ScanErrorKind,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
 #[allow(dead_code)]
 pub struct SqliteManager {
     conn: Mutex<Connection>,
+    events: EventBus,
 }
 
 #[allow(dead_code)]
@@ -34,9 +117,20 @@ impl SqliteManager {
 
         Ok(Self {
             conn: Mutex::new(conn),
+            events: EventBus::new(),
         })
     }
 
+    /// Koristi zajedničku sabirnicu događaja (preživljava reload konfiguracije).
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
     pub fn init_schema(&self, config: &Config) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
@@ -59,18 +153,34 @@ impl SqliteManager {
         for island_def in &config.islands {
             let mut virtual_fields = Vec::new();
 
-            for rel in &island_def.relations {
+            // Relacije `many` nemaju stupac, nego žive u tablici `island_links`.
+            for rel in island_def.relations.iter().filter(|r| r.cardinality == Cardinality::One) {
                 virtual_fields.push(crate::config::CloudField {
                     key: rel.field.clone(),
-                    field_type: "string".to_string(),
+                    field_type: FieldType::String,
                     required: false,
                     options: None,
                 });
             }
-            for agg in &island_def.aggregations {
+            let metric_names = island_def
+                .aggregations
+                .iter()
+                .map(|a| &a.name)
+                .chain(island_def.derived.iter().map(|d| &d.name));
+            for name in metric_names {
                 virtual_fields.push(crate::config::CloudField {
-                    key: agg.name.clone(),
-                    field_type: "number".to_string(),
+                    key: name.clone(),
+                    field_type: FieldType::Number,
+                    required: false,
+                    options: None,
+                });
+            }
+
+            virtual_fields.extend(island_def.fields.iter().cloned());
+            if island_def.capture_extra {
+                virtual_fields.push(crate::config::CloudField {
+                    key: EXTRA_COLUMN.to_string(),
+                    field_type: FieldType::String,
                     required: false,
                     options: None,
                 });
@@ -95,6 +205,68 @@ impl SqliteManager {
         ";
         conn.execute(pending_query, [])?;
 
+        // 4. ISLAND LINKS (relacije s kardinalnošću `many`, jedan redak po elementu liste)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS island_links (
+                island_table TEXT NOT NULL,
+                island_id TEXT NOT NULL,
+                field TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                value TEXT NOT NULL,
+                target_id TEXT,
+                PRIMARY KEY (island_table, island_id, field, position)
+            )",
+            [],
+        )?;
+
+        // 5. SCAN ERRORS (jedan redak po meta fajlu koji se ne da obraditi)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS scan_errors (
+                path TEXT PRIMARY KEY,
+                island_type TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                message TEXT NOT NULL,
+                line INTEGER,
+                column INTEGER,
+                first_seen TEXT NOT NULL,
+                last_seen TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // 6. TAGS (normalizirani tagovi i veza otok <-> tag)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE COLLATE NOCASE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS island_tags (
+                island_table TEXT NOT NULL,
+                island_id TEXT NOT NULL,
+                tag_id INTEGER NOT NULL,
+                PRIMARY KEY (island_table, island_id, tag_id)
+            )",
+            [],
+        )?;
+
+        // 7. AGGREGATION CACHE (izvučene vrijednosti po datoteci, po folderu otoka)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS aggregation_cache (
+                island_path TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                rule_key TEXT NOT NULL,
+                mtime INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                samples TEXT NOT NULL,
+                PRIMARY KEY (island_path, file_path, rule_key)
+            )",
+            [],
+        )?;
+
         Ok(())
     }
 
@@ -113,7 +285,23 @@ impl SqliteManager {
             > 0;
 
         if exists {
-            conn.execute(&format!("DELETE FROM {}", table_name), [])?;
+            conn.execute(
+                &format!(
+                    "DELETE FROM aggregation_cache WHERE island_path IN (SELECT path FROM {})",
+                    quote_ident(table_name)
+                ),
+                [],
+            )?;
+            conn.execute(&format!("DELETE FROM {}", quote_ident(table_name)), [])?;
+            conn.execute(
+                "DELETE FROM island_links WHERE island_table = ?",
+                params![table_name],
+            )?;
+            conn.execute(
+                "DELETE FROM island_tags WHERE island_table = ?",
+                params![table_name],
+            )?;
+            conn.execute(DELETE_UNUSED_TAGS, [])?;
             info!("🧹 Purged all data from Island table: {}", table_name);
         }
         Ok(())
@@ -142,15 +330,11 @@ impl SqliteManager {
             expected_cols.insert("path".to_string(), "TEXT".to_string());
             expected_cols.insert("status".to_string(), "TEXT".to_string());
             expected_cols.insert("updated_at".to_string(), "TEXT".to_string());
+            expected_cols.insert("parent_id".to_string(), "TEXT".to_string());
         }
 
         for field in fields {
-            let sql_type = match field.field_type.as_str() {
-                "number" => "REAL",
-                "boolean" => "INTEGER",
-                _ => "TEXT",
-            };
-            expected_cols.insert(field.key.clone(), sql_type.to_string());
+            expected_cols.insert(field.key.clone(), field.field_type.sql_type().to_string());
         }
 
         let table_exists: bool = conn
@@ -165,13 +349,18 @@ impl SqliteManager {
         if !table_exists {
             let mut cols_def = Vec::new();
             for (col, type_def) in &expected_cols {
-                cols_def.push(format!("{} {}", col, type_def));
+                cols_def.push(format!("{} {}", quote_ident(col), type_def));
             }
-            let query = format!("CREATE TABLE {} ({})", table_name, cols_def.join(", "));
+            let query = format!(
+                "CREATE TABLE {} ({})",
+                quote_ident(table_name),
+                cols_def.join(", ")
+            );
             info!("Database: Creating table '{}'", table_name);
             conn.execute(&query, [])?;
         } else {
-            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
+            let mut stmt =
+                conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table_name)))?;
             let existing_cols: HashSet<String> = stmt
                 .query_map([], |row| row.get::<_, String>(1))?
                 .filter_map(|r| r.ok())
@@ -182,7 +371,9 @@ impl SqliteManager {
                     let clean_type = type_def.replace("PRIMARY KEY", "");
                     let query = format!(
                         "ALTER TABLE {} ADD COLUMN {} {}",
-                        table_name, col, clean_type
+                        quote_ident(table_name),
+                        quote_ident(col),
+                        clean_type
                     );
                     info!(
                         "Database: Migrating '{}' -> Adding column '{}'",
@@ -207,7 +398,11 @@ impl SqliteManager {
         let conn = self.conn.lock().unwrap();
 
         // 1. Check Exact Match
-        let query_exact = format!("SELECT id FROM {} WHERE {} = ?", table, key_field);
+        let query_exact = format!(
+            "SELECT id FROM {} WHERE {} = ?",
+            quote_ident(table),
+            quote_ident(key_field)
+        );
         {
             let mut stmt = conn.prepare(&query_exact)?;
             let mut rows = stmt.query(params![value])?;
@@ -228,7 +423,12 @@ impl SqliteManager {
 
         // 3. Create Suggestions & New Action
         let mut suggestions = Vec::new();
-        if let Ok(mut stmt) = conn.prepare(&format!("SELECT {} FROM {}", key_field, table)) {
+        let query_names = format!(
+            "SELECT {} FROM {}",
+            quote_ident(key_field),
+            quote_ident(table)
+        );
+        if let Ok(mut stmt) = conn.prepare(&query_names) {
             // ISPRAVAK 3: Korištenje `flatten()`
             for existing_name in stmt.query_map([], |row| row.get::<_, String>(0))?.flatten() {
                 let dist = levenshtein(value, &existing_name);
@@ -263,6 +463,11 @@ impl SqliteManager {
             "Safety Valve: '{}' not found in {}. Action Created.",
             value, table
         );
+        self.events.publish(ValterEvent::PendingActionCreated {
+            action_id: action_id.clone(),
+            target_table: table.to_string(),
+            value: value.to_string(),
+        });
 
         if suggestions.is_empty() {
             Ok(EntityStatus::Pending(()))
@@ -271,7 +476,15 @@ impl SqliteManager {
         }
     }
 
-    pub fn approve_pending_creation(&self, action_id: &str) -> Result<String> {
+    /// Odobrava kreiranje entiteta. `extra` nadopunjuje ostala polja (npr. obavezni `status`).
+    /// Provjeravaju se samo poslana polja: zapis nastaje iz meta fajla, pa obavezna
+    /// polja koja nedostaju ostaju prazna dok ih korisnik ne popuni.
+    pub fn approve_pending_creation(
+        &self,
+        action_id: &str,
+        config: &Config,
+        extra: &Map<String, JsonValue>,
+    ) -> Result<String> {
         let mut conn = self.conn.lock().unwrap();
         let tx = conn.transaction()?;
 
@@ -287,10 +500,17 @@ impl SqliteManager {
             }
         };
 
-        let new_id = Uuid::new_v4().to_string();
-        let query_insert = format!("INSERT INTO {} (id, {}) VALUES (?, ?)", table, key_field);
+        let cloud_def = config
+            .clouds
+            .iter()
+            .find(|c| c.name == table)
+            .with_context(|| format!("Cloud '{}' is no longer defined in config", table))?;
+        let mut data = extra.clone();
+        data.insert(key_field, JsonValue::String(value.clone()));
+        let values = fields::validate_record(&cloud_def.fields, &data, true)?;
 
-        tx.execute(&query_insert, params![new_id, value])?;
+        let new_id = Uuid::new_v4().to_string();
+        Self::insert_row(&tx, &table, &new_id, values)?;
         tx.execute(
             "UPDATE pending_actions SET status = 'Resolved' WHERE id = ?",
             params![action_id],
@@ -298,9 +518,140 @@ impl SqliteManager {
 
         tx.commit()?;
         info!("Approved & Created: {} (ID: {})", value, new_id);
+        self.events.publish(ValterEvent::PendingActionResolved {
+            action_id: action_id.to_string(),
+            status: "Resolved".to_string(),
+        });
         Ok(new_id)
     }
 
+    fn insert_row(
+        conn: &Connection,
+        table: &str,
+        id: &str,
+        values: Vec<(String, SqlValue)>,
+    ) -> Result<()> {
+        let mut cols = vec![quote_ident("id")];
+        let mut vals = vec![SqlValue::Text(id.to_string())];
+        for (col, val) in values {
+            cols.push(quote_ident(&col));
+            vals.push(val);
+        }
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_ident(table),
+            cols.join(", "),
+            vec!["?"; cols.len()].join(", ")
+        );
+        conn.execute(&query, params_from_iter(vals))?;
+        Ok(())
+    }
+
+    fn cloud_definition<'a>(config: &'a Config, cloud: &str) -> Result<&'a CloudDefinition> {
+        config
+            .clouds
+            .iter()
+            .find(|c| c.name == cloud)
+            .with_context(|| format!("Unknown cloud '{}'", cloud))
+    }
+
+    /// Kreira novi Cloud entitet nakon validacije svih polja. Vraća novi UUID.
+    pub fn create_cloud_entity(
+        &self,
+        config: &Config,
+        cloud: &str,
+        data: &Map<String, JsonValue>,
+    ) -> Result<String> {
+        let def = Self::cloud_definition(config, cloud)?;
+        let values = fields::validate_record(&def.fields, data, false)?;
+
+        let conn = self.conn.lock().unwrap();
+        let new_id = Uuid::new_v4().to_string();
+        Self::insert_row(&conn, cloud, &new_id, values)?;
+        info!("Cloud Entity Created: {} (ID: {})", cloud, new_id);
+        Ok(new_id)
+    }
+
+    /// Ažurira samo poslana polja postojećeg entiteta.
+    pub fn update_cloud_entity(
+        &self,
+        config: &Config,
+        cloud: &str,
+        id: &str,
+        data: &Map<String, JsonValue>,
+    ) -> Result<()> {
+        let def = Self::cloud_definition(config, cloud)?;
+        let values = fields::validate_record(&def.fields, data, true)?;
+        if values.is_empty() {
+            anyhow::bail!("Nothing to update.");
+        }
+
+        let assignments: Vec<String> =
+            values.iter().map(|(col, _)| format!("{} = ?", quote_ident(col))).collect();
+        let mut params: Vec<SqlValue> = values.into_iter().map(|(_, v)| v).collect();
+        params.push(SqlValue::Text(id.to_string()));
+
+        let conn = self.conn.lock().unwrap();
+        let query = format!(
+            "UPDATE {} SET {} WHERE id = ?",
+            quote_ident(cloud),
+            assignments.join(", ")
+        );
+        if conn.execute(&query, params_from_iter(params))? == 0 {
+            anyhow::bail!("{} '{}' not found.", cloud, id);
+        }
+        info!("Cloud Entity Updated: {} (ID: {})", cloud, id);
+        Ok(())
+    }
+
+    /// Briše entitet, osim ako ga neki Island još referencira kroz relaciju.
+    pub fn delete_cloud_entity(&self, config: &Config, cloud: &str, id: &str) -> Result<()> {
+        Self::cloud_definition(config, cloud)?;
+        let conn = self.conn.lock().unwrap();
+
+        let mut references = Vec::new();
+        for island in &config.islands {
+            for rel in island.relations.iter().filter(|r| r.target_cloud == cloud) {
+                let count: i64 = match rel.cardinality {
+                    Cardinality::One => {
+                        let query = format!(
+                            "SELECT count(*) FROM {} WHERE {} = ?",
+                            quote_ident(&island.name),
+                            quote_ident(&rel.field)
+                        );
+                        conn.query_row(&query, params![id], |row| row.get(0)).unwrap_or(0)
+                    }
+                    Cardinality::Many => conn
+                        .query_row(
+                            "SELECT count(DISTINCT island_id) FROM island_links
+                             WHERE island_table = ? AND field = ? AND target_id = ?",
+                            params![island.name, rel.field, id],
+                            |row| row.get(0),
+                        )
+                        .unwrap_or(0),
+                };
+                if count > 0 {
+                    references.push(format!("{}.{} ({})", island.name, rel.field, count));
+                }
+            }
+        }
+        if !references.is_empty() {
+            anyhow::bail!(
+                "{} '{}' is still referenced by: {}",
+                cloud,
+                id,
+                references.join(", ")
+            );
+        }
+
+        let query = format!("DELETE FROM {} WHERE id = ?", quote_ident(cloud));
+        if conn.execute(&query, params![id])? == 0 {
+            anyhow::bail!("{} '{}' not found.", cloud, id);
+        }
+        info!("Cloud Entity Deleted: {} (ID: {})", cloud, id);
+        Ok(())
+    }
+
     pub fn reject_pending_action(&self, action_id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
@@ -308,70 +659,434 @@ impl SqliteManager {
             params![action_id],
         )?;
         info!("Action Rejected: {}", action_id);
+        self.events.publish(ValterEvent::PendingActionResolved {
+            action_id: action_id.to_string(),
+            status: "Rejected".to_string(),
+        });
         Ok(())
     }
 
+    /// Upisuje otok pod stabilnim `id`. Postojeći redak se ažurira na mjestu
+    /// (preimenovanje, premještanje), a redak s istom putanjom pod drugim ID-em
+    /// (npr. iz vremena kad su otoci bili ključani po imenu) preuzima novi ID.
+    /// `parent_id` je ID najbližeg otoka iznad ovog foldera (pod-projekt), a
+    /// `fields` već validirane vrijednosti polja iz meta fajla.
+    #[allow(clippy::too_many_arguments)]
     pub fn upsert_island(
         &self,
         table: &str,
+        id: &str,
         name: &str,
         path: &str,
+        parent_id: Option<&str>,
+        fields: &[(String, SqlValue)],
         relations: &HashMap<String, Option<String>>,
-        aggregations: &HashMap<String, f64>,
+        aggregations: &HashMap<String, AggregationValue>,
     ) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let quoted_table = quote_ident(table);
 
-        // Check ID
-        let query_select = format!("SELECT id FROM {} WHERE name = ?", table);
-        let project_id: String = {
-            let mut stmt = conn.prepare(&query_select)?;
-            let mut rows = stmt.query(params![name])?;
-            if let Some(row) = rows.next()? {
-                row.get(0)?
-            } else {
-                Uuid::new_v4().to_string()
+        let previous: Option<(String, String)> = tx
+            .query_row(
+                &format!("SELECT name, path FROM {} WHERE id = ?", quoted_table),
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let mut previous_name = None;
+        if let Some((old_name, old_path)) = previous {
+            // Premješteni otok: predmemorija je vezana uz staru putanju.
+            if old_path != path {
+                tx.execute(
+                    "DELETE FROM aggregation_cache WHERE island_path = ?",
+                    params![old_path],
+                )?;
+            }
+            previous_name = Some(old_name);
+        }
+        if previous_name.is_none() {
+            let legacy: Option<(String, String)> = tx
+                .query_row(
+                    &format!(
+                        "SELECT id, name FROM {} WHERE path = ? LIMIT 1",
+                        quoted_table
+                    ),
+                    params![path],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            if let Some((old_id, old_name)) = legacy {
+                tx.execute(
+                    &format!("UPDATE {} SET id = ? WHERE id = ?", quoted_table),
+                    params![id, old_id],
+                )?;
+                tx.execute(
+                    "UPDATE island_links SET island_id = ? WHERE island_table = ? AND island_id = ?",
+                    params![id, table, old_id],
+                )?;
+                tx.execute(
+                    "UPDATE island_tags SET island_id = ? WHERE island_table = ? AND island_id = ?",
+                    params![id, table, old_id],
+                )?;
+                info!("🔑 Island '{}' re-keyed: {} -> {}", old_name, old_id, id);
+                previous_name = Some(old_name);
             }
+        }
+        if let Some(old) = previous_name.as_deref().filter(|old| *old != name) {
+            info!("✏️  Island renamed: '{}' -> '{}' ({})", old, name, id);
+        }
+
+        let now = chrono::Local::now().to_rfc3339();
+        let mut cols = vec!["name", "path", "updated_at", "parent_id"];
+        let mut vals: Vec<SqlValue> = vec![
+            SqlValue::Text(name.to_string()),
+            SqlValue::Text(path.to_string()),
+            SqlValue::Text(now),
+            parent_id.map_or(SqlValue::Null, |p| SqlValue::Text(p.to_string())),
+        ];
+        for (k, v) in fields {
+            cols.push(k);
+            vals.push(v.clone());
+        }
+        for (k, v) in relations {
+            cols.push(k);
+            vals.push(v.clone().map_or(SqlValue::Null, SqlValue::Text));
+        }
+        for (k, v) in aggregations {
+            cols.push(k);
+            vals.push(v.into());
+        }
+
+        let query = if previous_name.is_some() {
+            let assignments: Vec<String> =
+                cols.iter().map(|c| format!("{} = ?", quote_ident(c))).collect();
+            vals.push(SqlValue::Text(id.to_string()));
+            format!(
+                "UPDATE {} SET {} WHERE id = ?",
+                quoted_table,
+                assignments.join(", ")
+            )
+        } else {
+            cols.insert(0, "id");
+            vals.insert(0, SqlValue::Text(id.to_string()));
+            let quoted_cols: Vec<String> = cols.iter().map(|c| quote_ident(c)).collect();
+            format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                quoted_table,
+                quoted_cols.join(", "),
+                vec!["?"; cols.len()].join(", ")
+            )
         };
+        tx.execute(&query, params_from_iter(vals))?;
+        // Otok koji se vratio na disk više nije "Missing".
+        tx.execute(
+            &format!(
+                "UPDATE {} SET status = NULL WHERE id = ? AND status = 'Missing'",
+                quoted_table
+            ),
+            params![id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
 
-        // Delete old entry to ensure clean upsert (simpler than UPDATE for dynamic fields)
-        conn.execute(
-            &format!("DELETE FROM {} WHERE id = ?", table),
-            params![project_id],
+    /// Briše otok, sve njegove `many` relacije i predmemoriju agregacija.
+    pub fn delete_island(&self, table: &str, id: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            &format!(
+                "DELETE FROM aggregation_cache WHERE island_path IN (SELECT path FROM {} WHERE id = ?)",
+                quote_ident(table)
+            ),
+            params![id],
         )?;
+        tx.execute(
+            &format!("DELETE FROM {} WHERE id = ?", quote_ident(table)),
+            params![id],
+        )?;
+        tx.execute(
+            "DELETE FROM island_links WHERE island_table = ? AND island_id = ?",
+            params![table, id],
+        )?;
+        tx.execute(
+            "DELETE FROM island_tags WHERE island_table = ? AND island_id = ?",
+            params![table, id],
+        )?;
+        tx.execute(DELETE_UNUSED_TAGS, [])?;
+        tx.commit()?;
+        Ok(())
+    }
 
-        let mut final_cols = vec![
-            "id".to_string(),
-            "name".to_string(),
-            "path".to_string(),
-            "updated_at".to_string(),
-        ];
+    /// Zamjenjuje tagove otoka. Tag koji više nijedan otok ne koristi se briše.
+    pub fn set_island_tags(&self, table: &str, island_id: &str, tags: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM island_tags WHERE island_table = ? AND island_id = ?",
+            params![table, island_id],
+        )?;
+        for tag in tags {
+            tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?)", params![tag])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO island_tags (island_table, island_id, tag_id)
+                 SELECT ?, ?, id FROM tags WHERE name = ?",
+                params![table, island_id, tag],
+            )?;
+        }
+        tx.execute(DELETE_UNUSED_TAGS, [])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Tagovi jednog otoka, abecedno.
+    pub fn fetch_island_tags(&self, table: &str, island_id: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT t.name FROM island_tags it JOIN tags t ON t.id = it.tag_id
+             WHERE it.island_table = ? AND it.island_id = ? ORDER BY t.name",
+        )?;
+        let tags = stmt
+            .query_map(params![table, island_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(tags)
+    }
+
+    /// Dodaje svakom retku otoka polje `tags` (lista imena).
+    pub fn attach_island_tags(&self, table: &str, rows: &mut [JsonValue]) -> Result<()> {
+        for row in rows.iter_mut() {
+            let Some(id) = row["id"].as_str().map(str::to_string) else {
+                continue;
+            };
+            row["tags"] = serde_json::json!(self.fetch_island_tags(table, &id)?);
+        }
+        Ok(())
+    }
+
+    /// Svi tagovi s brojem otoka (`name`, `count`), najčešći prvi.
+    /// `island_type` broji samo otoke tog tipa.
+    pub fn fetch_tag_counts(&self, island_type: Option<&str>) -> Result<Vec<JsonValue>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT t.name, COUNT(*) FROM island_tags it JOIN tags t ON t.id = it.tag_id
+             WHERE ?1 IS NULL OR it.island_table = ?1
+             GROUP BY t.id ORDER BY COUNT(*) DESC, t.name",
+        )?;
+        let tags = stmt
+            .query_map(params![island_type], |row| {
+                Ok(serde_json::json!({
+                    "name": row.get::<_, String>(0)?,
+                    "count": row.get::<_, i64>(1)?,
+                }))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(tags)
+    }
+
+    /// Bilježi grešku obrade; ponovljena greška za isti fajl zadržava `first_seen`.
+    pub fn record_scan_error(&self, error: &ScanError) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
         let now = chrono::Local::now().to_rfc3339();
-        let mut final_vals = vec![
-            format!("'{}'", project_id),
-            format!("'{}'", name),
-            format!("'{}'", path),
-            format!("'{}'", now),
-        ];
+        conn.execute(
+            "INSERT INTO scan_errors
+             (path, island_type, kind, message, line, column, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+             ON CONFLICT(path) DO UPDATE SET
+                island_type = excluded.island_type,
+                kind = excluded.kind,
+                message = excluded.message,
+                line = excluded.line,
+                column = excluded.column,
+                last_seen = excluded.last_seen",
+            params![
+                error.path,
+                error.island_type,
+                error.kind.as_str(),
+                error.message,
+                error.line.map(|l| l as i64),
+                error.column.map(|c| c as i64),
+                now
+            ],
+        )?;
+        Ok(())
+    }
 
-        for (k, v) in relations {
-            final_cols.push(k.clone());
-            match v {
-                Some(uuid) => final_vals.push(format!("'{}'", uuid)),
-                None => final_vals.push("NULL".to_string()),
+    /// Briše grešku za ispravljeni (ili obrisani) fajl. Vraća je li je bilo.
+    pub fn clear_scan_error(&self, path: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("DELETE FROM scan_errors WHERE path = ?", params![path])? > 0)
+    }
+
+    /// Trenutne greške obrade, po putanji; opcionalno samo za jedan tip otoka.
+    pub fn fetch_scan_errors(&self, island_type: Option<&str>) -> Result<Vec<JsonValue>> {
+        let query = DataQuery {
+            filters: island_type
+                .map(|t| DataFilter {
+                    field: "island_type".to_string(),
+                    op: FilterOp::Eq,
+                    value: SqlValue::Text(t.to_string()),
+                })
+                .into_iter()
+                .collect(),
+            sort: vec![SortKey {
+                field: "path".to_string(),
+                descending: false,
+            }],
+            ..DataQuery::default()
+        };
+        self.fetch_dynamic("scan_errors", &query)
+    }
+
+    /// Predmemorija agregacija za otok u folderu `island_path`.
+    pub fn load_file_cache(&self, island_path: &str) -> Result<FileCache> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT file_path, rule_key, mtime, size, hash, samples
+             FROM aggregation_cache WHERE island_path = ?",
+        )?;
+        let entries = stmt
+            .query_map(params![island_path], |row| {
+                Ok((
+                    (row.get(0)?, row.get(1)?),
+                    CachedFile {
+                        mtime: row.get(2)?,
+                        size: row.get(3)?,
+                        hash: row.get(4)?,
+                        samples: row.get(5)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+        Ok(FileCache::new(entries))
+    }
+
+    /// Upisuje samo promjene iz zadnjeg izračuna: nove/osvježene unose i brisanje
+    /// unosa za datoteke koje više nisu pronađene.
+    pub fn save_file_cache(&self, island_path: &str, cache: &FileCache) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut upsert = tx.prepare(
+                "INSERT OR REPLACE INTO aggregation_cache
+                 (island_path, file_path, rule_key, mtime, size, hash, samples)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )?;
+            for ((file_path, rule_key), entry) in cache.changed() {
+                upsert.execute(params![
+                    island_path,
+                    file_path,
+                    rule_key,
+                    entry.mtime,
+                    entry.size,
+                    entry.hash,
+                    entry.samples
+                ])?;
+            }
+            let mut delete = tx.prepare(
+                "DELETE FROM aggregation_cache
+                 WHERE island_path = ? AND file_path = ? AND rule_key = ?",
+            )?;
+            for (file_path, rule_key) in cache.stale() {
+                delete.execute(params![island_path, file_path, rule_key])?;
             }
         }
-        for (k, v) in aggregations {
-            final_cols.push(k.clone());
-            final_vals.push(format!("{}", v));
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// ID otoka tipa `table` koji živi u folderu `path`.
+    pub fn find_island_id_by_path(&self, table: &str, path: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!(
+                    "SELECT id FROM {} WHERE path = ? LIMIT 1",
+                    quote_ident(table)
+                ),
+                params![path],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Arhivira otok čiji folder je nestao (`status = 'Missing'`).
+    pub fn mark_island_missing(&self, table: &str, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!(
+                "UPDATE {} SET status = 'Missing', updated_at = ? WHERE id = ?",
+                quote_ident(table)
+            ),
+            params![chrono::Local::now().to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Zamjenjuje sve elemente `many` relacije jednog otoka. Svaki element je par
+    /// (vrijednost iz meta fajla, UUID ako je entitet pronađen).
+    pub fn set_island_links(
+        &self,
+        table: &str,
+        island_id: &str,
+        field: &str,
+        links: &[(String, Option<String>)],
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM island_links WHERE island_table = ? AND island_id = ? AND field = ?",
+            params![table, island_id, field],
+        )?;
+        for (position, (value, target_id)) in links.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO island_links (island_table, island_id, field, position, value, target_id)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+                params![table, island_id, field, position as i64, value, target_id],
+            )?;
         }
+        tx.commit()?;
+        Ok(())
+    }
 
-        let query = format!(
-            "INSERT INTO {} ({}) VALUES ({})",
-            table,
-            final_cols.join(", "),
-            final_vals.join(", ")
-        );
-        conn.execute(&query, [])?;
+    /// UUID-ovi razriješenih elemenata `many` relacije, redom iz meta fajla.
+    pub fn fetch_island_links(
+        &self,
+        table: &str,
+        island_id: &str,
+        field: &str,
+    ) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT target_id FROM island_links
+             WHERE island_table = ? AND island_id = ? AND field = ? AND target_id IS NOT NULL
+             ORDER BY position",
+        )?;
+        let ids = stmt
+            .query_map(params![table, island_id, field], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+
+    /// Dodaje svakom retku otoka `many` relacije kao liste UUID-ova.
+    pub fn attach_island_links(
+        &self,
+        config: &Config,
+        island: &str,
+        rows: &mut [JsonValue],
+    ) -> Result<()> {
+        let Some(def) = config.islands.iter().find(|i| i.name == island) else {
+            return Ok(());
+        };
+        for rel in def.relations.iter().filter(|r| r.cardinality == Cardinality::Many) {
+            for row in rows.iter_mut() {
+                let Some(id) = row.get("id").and_then(JsonValue::as_str) else {
+                    continue;
+                };
+                let ids = self.fetch_island_links(island, id, &rel.field)?;
+                row[&rel.field] = JsonValue::from(ids);
+            }
+        }
         Ok(())
     }
 
@@ -416,17 +1131,217 @@ impl SqliteManager {
     }
 
     pub fn fetch_all_dynamic(&self, table: &str) -> Result<Vec<JsonValue>> {
-        let conn = self.conn.lock().unwrap();
-        let query = format!("SELECT * FROM {}", table);
+        self.fetch_dynamic(table, &DataQuery::default())
+    }
 
-        let mut stmt = match conn.prepare(&query) {
-            Ok(s) => s,
-            Err(_) => return Ok(vec![]),
+    pub fn fetch_by_id(&self, table: &str, id: &str) -> Result<Option<JsonValue>> {
+        let query = DataQuery {
+            filters: vec![DataFilter {
+                field: "id".to_string(),
+                op: FilterOp::Eq,
+                value: SqlValue::Text(id.to_string()),
+            }],
+            limit: Some(1),
+            ..DataQuery::default()
         };
+        Ok(self.fetch_dynamic(table, &query)?.into_iter().next())
+    }
+
+    /// Zamjenjuje UUID-ove relacija punim zapisom ciljanog Clouda.
+    /// Relacija na čekanju (NULL) ili obrisan cilj ostaju `null`.
+    pub fn expand_relations(
+        &self,
+        config: &Config,
+        island: &str,
+        rows: &mut [JsonValue],
+    ) -> Result<()> {
+        let Some(def) = config.islands.iter().find(|i| i.name == island) else {
+            return Ok(());
+        };
+        for rel in &def.relations {
+            let mut targets: HashMap<String, JsonValue> = HashMap::new();
+            let mut resolve = |id: &str| -> Result<JsonValue> {
+                if !targets.contains_key(id) {
+                    let target = self.fetch_by_id(&rel.target_cloud, id)?;
+                    targets.insert(id.to_string(), target.unwrap_or(JsonValue::Null));
+                }
+                Ok(targets[id].clone())
+            };
+            for row in rows.iter_mut() {
+                // `many` relacije su liste UUID-ova (vidi `attach_island_links`).
+                let expanded = match row.get(&rel.field) {
+                    Some(JsonValue::String(id)) => resolve(id)?,
+                    Some(JsonValue::Array(ids)) => JsonValue::Array(
+                        ids.iter()
+                            .filter_map(JsonValue::as_str)
+                            .map(&mut resolve)
+                            .collect::<Result<Vec<_>>>()?,
+                    ),
+                    _ => continue,
+                };
+                row[&rel.field] = expanded;
+            }
+        }
+        Ok(())
+    }
+
+    /// Otoci koji referenciraju zadani Cloud zapis, grupirani po tipu otoka
+    /// (npr. svi projekti jednog klijenta).
+    pub fn fetch_referrers(
+        &self,
+        config: &Config,
+        cloud: &str,
+        id: &str,
+    ) -> Result<Map<String, JsonValue>> {
+        let mut referrers = Map::new();
+        for island in &config.islands {
+            let mut items: Vec<JsonValue> = Vec::new();
+            let mut relations =
+                island.relations.iter().filter(|r| r.target_cloud == cloud).peekable();
+            if relations.peek().is_none() {
+                continue;
+            }
+            for rel in relations {
+                for row in
+                    self.fetch_referrers_by(&island.name, rel.field.as_str(), rel.cardinality, id)?
+                {
+                    if !items.iter().any(|i| i["id"] == row["id"]) {
+                        items.push(row);
+                    }
+                }
+            }
+            referrers.insert(island.name.clone(), JsonValue::Array(items));
+        }
+        Ok(referrers)
+    }
+
+    /// Svi otoci kao stablo po `parent_id`. Svaki čvor nosi `_type` i `children`;
+    /// otok čiji roditelj ne postoji (obrisan) postaje korijen. `island_type`
+    /// ograničava korijene na jedan tip, a djeca mogu biti bilo kojeg tipa.
+    pub fn fetch_island_tree(
+        &self,
+        config: &Config,
+        island_type: Option<&str>,
+    ) -> Result<Vec<JsonValue>> {
+        let mut nodes: Vec<JsonValue> = Vec::new();
+        for island in &config.islands {
+            for mut row in self.fetch_all_dynamic(&island.name)? {
+                row["_type"] = JsonValue::String(island.name.clone());
+                nodes.push(row);
+            }
+        }
+        nodes.sort_by(|a, b| a["path"].as_str().cmp(&b["path"].as_str()));
+
+        let ids: HashSet<&str> = nodes.iter().filter_map(|n| n["id"].as_str()).collect();
+        let mut children: HashMap<&str, Vec<&JsonValue>> = HashMap::new();
+        let mut roots = Vec::new();
+        for node in &nodes {
+            match node["parent_id"].as_str().filter(|p| ids.contains(p)) {
+                Some(parent) => children.entry(parent).or_default().push(node),
+                None => roots.push(node),
+            }
+        }
+
+        fn build<'a>(
+            node: &'a JsonValue,
+            children: &HashMap<&str, Vec<&'a JsonValue>>,
+            visited: &mut HashSet<&'a str>,
+        ) -> JsonValue {
+            let mut out = node.clone();
+            let id = node["id"].as_str().unwrap_or_default();
+            let kids: Vec<JsonValue> = if visited.insert(id) {
+                children
+                    .get(id)
+                    .into_iter()
+                    .flatten()
+                    .map(|child| build(child, children, visited))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            out["children"] = JsonValue::Array(kids);
+            out
+        }
+
+        let mut visited = HashSet::new();
+        Ok(roots
+            .into_iter()
+            .filter(|r| island_type.is_none_or(|t| r["_type"] == t))
+            .map(|r| build(r, &children, &mut visited))
+            .collect())
+    }
+
+    /// Otoci tipa `island` čija relacija `field` pokazuje na Cloud zapis `id`.
+    pub fn fetch_referrers_by(
+        &self,
+        island: &str,
+        field: &str,
+        cardinality: Cardinality,
+        id: &str,
+    ) -> Result<Vec<JsonValue>> {
+        match cardinality {
+            Cardinality::One => {
+                let query = DataQuery {
+                    filters: vec![DataFilter {
+                        field: field.to_string(),
+                        op: FilterOp::Eq,
+                        value: SqlValue::Text(id.to_string()),
+                    }],
+                    ..DataQuery::default()
+                };
+                self.fetch_dynamic(island, &query)
+            }
+            Cardinality::Many => {
+                let island_ids: Vec<String> = {
+                    let conn = self.conn.lock().unwrap();
+                    let mut stmt = conn.prepare(
+                        "SELECT DISTINCT island_id FROM island_links
+                         WHERE island_table = ? AND field = ? AND target_id = ?",
+                    )?;
+                    let ids = stmt
+                        .query_map(params![island, field, id], |row| row.get(0))?
+                        .collect::<rusqlite::Result<Vec<String>>>()?;
+                    ids
+                };
+                let mut rows = Vec::new();
+                for island_id in island_ids {
+                    rows.extend(self.fetch_by_id(island, &island_id)?);
+                }
+                Ok(rows)
+            }
+        }
+    }
+
+    /// Dohvaća retke uz filtere, sortiranje i paginaciju. Sve se prevodi u parametrizirani SQL.
+    pub fn fetch_dynamic(&self, table: &str, query: &DataQuery) -> Result<Vec<JsonValue>> {
+        let conn = self.conn.lock().unwrap();
+        let columns = Self::table_columns(&conn, table)?;
+        if columns.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let (where_sql, mut params) = Self::where_clause(table, &columns, &query.filters)?;
+        let mut sql = format!("SELECT * FROM {}{}", quote_ident(table), where_sql);
+
+        let mut order = Vec::new();
+        for key in &query.sort {
+            Self::check_column(&columns, &key.field)?;
+            let dir = if key.descending { "DESC" } else { "ASC" };
+            order.push(format!("{} {}", quote_ident(&key.field), dir));
+        }
+        // `id` kao zadnji ključ daje stabilan redoslijed, pa se stranice ne preklapaju.
+        order.push("\"id\" ASC".to_string());
+        sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+        if query.limit.is_some() || query.offset.is_some() {
+            sql.push_str(" LIMIT ? OFFSET ?");
+            params.push(SqlValue::Integer(query.limit.map_or(-1, |l| l as i64)));
+            params.push(SqlValue::Integer(query.offset.unwrap_or(0) as i64));
+        }
 
+        let mut stmt = conn.prepare(&sql)?;
         let col_names: Vec<String> =
             stmt.column_names().into_iter().map(std::string::ToString::to_string).collect();
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params_from_iter(params), |row| {
             let mut map = Map::new();
             // ISPRAVAK 2: Korištenje `enumerate()` umjesto `for i in 0..len()`
             for (i, name) in col_names.iter().enumerate() {
@@ -451,4 +1366,430 @@ impl SqliteManager {
         }
         Ok(results)
     }
+
+    /// Ukupan broj redaka koji odgovaraju filterima (bez paginacije).
+    pub fn count_dynamic(&self, table: &str, filters: &[DataFilter]) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let columns = Self::table_columns(&conn, table)?;
+        if columns.is_empty() {
+            return Ok(0);
+        }
+        let (where_sql, params) = Self::where_clause(table, &columns, filters)?;
+        let sql = format!("SELECT count(*) FROM {}{}", quote_ident(table), where_sql);
+        Ok(conn.query_row(&sql, params_from_iter(params), |row| row.get(0))?)
+    }
+
+    fn table_columns(conn: &Connection, table: &str) -> Result<HashSet<String>> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table)))?;
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(columns)
+    }
+
+    fn check_column(columns: &HashSet<String>, field: &str) -> Result<()> {
+        if !columns.contains(field) {
+            anyhow::bail!("Unknown field '{}'", field);
+        }
+        Ok(())
+    }
+
+    fn where_clause(
+        table: &str,
+        columns: &HashSet<String>,
+        filters: &[DataFilter],
+    ) -> Result<(String, Vec<SqlValue>)> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+
+        for filter in filters {
+            if filter.op == FilterOp::HasTag {
+                conditions.push(
+                    "\"id\" IN (SELECT it.island_id FROM island_tags it
+                     JOIN tags t ON t.id = it.tag_id
+                     WHERE it.island_table = ? AND t.name = ?)"
+                        .to_string(),
+                );
+                params.push(SqlValue::Text(table.to_string()));
+                params.push(filter.value.clone());
+                continue;
+            }
+            Self::check_column(columns, &filter.field)?;
+            let col = quote_ident(&filter.field);
+            let (condition, param) = match (filter.op, &filter.value) {
+                (FilterOp::Eq, SqlValue::Null) => (format!("{} IS NULL", col), None),
+                (FilterOp::Ne, SqlValue::Null) => (format!("{} IS NOT NULL", col), None),
+                (FilterOp::Contains, value) => {
+                    let text = match value {
+                        SqlValue::Text(s) => s.clone(),
+                        SqlValue::Integer(i) => i.to_string(),
+                        SqlValue::Real(f) => f.to_string(),
+                        _ => String::new(),
+                    };
+                    let escaped =
+                        text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                    (
+                        format!("{} LIKE ? ESCAPE '\\'", col),
+                        Some(SqlValue::Text(format!("%{}%", escaped))),
+                    )
+                }
+                (op, value) => {
+                    let op = match op {
+                        FilterOp::Ne => "IS NOT",
+                        FilterOp::Gt => ">",
+                        FilterOp::Gte => ">=",
+                        FilterOp::Lt => "<",
+                        FilterOp::Lte => "<=",
+                        _ => "=",
+                    };
+                    (format!("{} {} ?", col, op), Some(value.clone()))
+                }
+            };
+            conditions.push(condition);
+            params.extend(param);
+        }
+
+        let sql = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        Ok((sql, params))
+    }
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+GLOBAL:
+  company_name: "Test"
+  currency_symbol: "$"
+  locale: "en_US"
+CLOUDS:
+  - name: "Order"
+    icon: "x"
+    fields:
+      - key: "group"
+        type: "string"
+ISLANDS:
+  - name: "My Projects"
+    root_path: "./projects/*"
+    meta_file: "meta.yaml"
+    relations:
+      - field: "select"
+        target_cloud: "Order"
+    aggregations:
+      - name: "total sum"
+        path: "*.yaml"
+        target_field: "amount"
+        logic: "sum"
+"#;
+
+    #[test]
+    fn test_quoted_identifiers_and_bound_values() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let db = SqliteManager::new(":memory:").unwrap();
+        db.init_schema(&config).unwrap();
+        // Ponovljena migracija mora proći i nad postojećim tablicama.
+        db.init_schema(&config).unwrap();
+
+        let hostile = "x'); DROP TABLE \"Order\"; --";
+        let status = db.check_or_create_pending("Order", "group", hostile, "{}").unwrap();
+        assert!(matches!(status, EntityStatus::Pending(())));
+
+        let action_id = db.fetch_pending_actions().unwrap()[0]["id"].as_str().unwrap().to_string();
+        let client_id = db.approve_pending_creation(&action_id, &config, &Map::new()).unwrap();
+        let found = db.check_or_create_pending("Order", "group", hostile, "{}").unwrap();
+        assert!(matches!(found, EntityStatus::Found(id) if id == client_id));
+
+        let mut relations = HashMap::new();
+        relations.insert("select".to_string(), Some(client_id.clone()));
+        let mut aggregations = HashMap::new();
+        aggregations.insert("total sum".to_string(), AggregationValue::Number(42.0));
+        db.upsert_island(
+            "My Projects",
+            "p1",
+            "O'Brien Ltd",
+            "/data/O'Brien \"Ltd\"",
+            None,
+            &[],
+            &relations,
+            &aggregations,
+        )
+        .unwrap();
+
+        let rows = db.fetch_all_dynamic("My Projects").unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["name"], "O'Brien Ltd");
+        assert_eq!(rows[0]["path"], "/data/O'Brien \"Ltd\"");
+        assert_eq!(rows[0]["select"], client_id.as_str());
+        assert_eq!(rows[0]["total sum"], 42.0);
+        assert_eq!(db.fetch_all_dynamic("Order").unwrap().len(), 1);
+
+        db.purge_islands("My Projects").unwrap();
+        assert!(db.fetch_all_dynamic("My Projects").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_approve_without_data_leaves_required_fields_empty() {
+        let config: Config = serde_yaml::from_str(
+            &CONFIG.replace(
+                "      - key: \"group\"\n        type: \"string\"\n",
+                "      - key: \"group\"\n        type: \"string\"\n      - key: \"status\"\n        type: \"select\"\n        options: [\"Active\", \"Lead\"]\n        required: true\n",
+            ),
+        )
+        .unwrap();
+        assert!(config.clouds[0].fields[1].required);
+        let db = SqliteManager::new(":memory:").unwrap();
+        db.init_schema(&config).unwrap();
+
+        db.check_or_create_pending("Order", "group", "A", "{}").unwrap();
+        let action_id = db.fetch_pending_actions().unwrap()[0]["id"].as_str().unwrap().to_string();
+        let id = db.approve_pending_creation(&action_id, &config, &Map::new()).unwrap();
+        let row = db.fetch_by_id("Order", &id).unwrap().unwrap();
+        assert_eq!(row["group"], "A");
+        assert!(row["status"].is_null());
+
+        // Poslana polja se i dalje validiraju.
+        db.check_or_create_pending("Order", "group", "B", "{}").unwrap();
+        let action_id = db.fetch_pending_actions().unwrap()[0]["id"].as_str().unwrap().to_string();
+        let bad = serde_json::json!({"status": "Unknown"});
+        assert!(db
+            .approve_pending_creation(&action_id, &config, bad.as_object().unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_cloud_entity_crud_and_referenced_delete() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let db = SqliteManager::new(":memory:").unwrap();
+        db.init_schema(&config).unwrap();
+
+        let data = |v: serde_json::Value| v.as_object().unwrap().clone();
+        let id = db
+            .create_cloud_entity(&config, "Order", &data(serde_json::json!({"group": "A"})))
+            .unwrap();
+        assert!(db
+            .create_cloud_entity(&config, "Order", &data(serde_json::json!({"nope": 1})))
+            .is_err());
+
+        db.update_cloud_entity(
+            &config,
+            "Order",
+            &id,
+            &data(serde_json::json!({"group": "B"})),
+        )
+        .unwrap();
+        assert_eq!(db.fetch_all_dynamic("Order").unwrap()[0]["group"], "B");
+        assert!(db
+            .update_cloud_entity(
+                &config,
+                "Order",
+                "missing",
+                &data(serde_json::json!({"group": "C"}))
+            )
+            .is_err());
+
+        let mut relations = HashMap::new();
+        relations.insert("select".to_string(), Some(id.clone()));
+        db.upsert_island(
+            "My Projects",
+            "p1",
+            "P1",
+            "/p1",
+            None,
+            &[],
+            &relations,
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let err = db.delete_cloud_entity(&config, "Order", &id).unwrap_err().to_string();
+        assert!(err.contains("My Projects.select (1)"));
+
+        db.purge_islands("My Projects").unwrap();
+        db.delete_cloud_entity(&config, "Order", &id).unwrap();
+        assert!(db.fetch_all_dynamic("Order").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_island_tree_nests_children_under_parent() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let db = SqliteManager::new(":memory:").unwrap();
+        db.init_schema(&config).unwrap();
+
+        // P2 je pod-projekt od P1.
+        for (id, name, parent) in [("p1", "P1", None), ("p2", "P2", Some("p1"))] {
+            let path = format!("/{}", id);
+            db.upsert_island(
+                "My Projects",
+                id,
+                name,
+                &path,
+                parent,
+                &[],
+                &HashMap::new(),
+                &HashMap::new(),
+            )
+            .unwrap();
+        }
+
+        let tree = db.fetch_island_tree(&config, None).unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0]["name"], "P1");
+        assert_eq!(tree[0]["children"][0]["name"], "P2");
+        assert_eq!(tree[0]["children"][0]["_type"], "My Projects");
+    }
+
+    #[test]
+    fn test_expand_relations_and_referrers() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let db = SqliteManager::new(":memory:").unwrap();
+        db.init_schema(&config).unwrap();
+
+        let data = serde_json::json!({"group": "B"});
+        let id = db.create_cloud_entity(&config, "Order", data.as_object().unwrap()).unwrap();
+        let mut relations = HashMap::new();
+        relations.insert("select".to_string(), Some(id.clone()));
+        for (island_id, name, relations) in [("p1", "P1", relations), ("p2", "P2", HashMap::new())]
+        {
+            let path = format!("/{}", island_id);
+            db.upsert_island(
+                "My Projects",
+                island_id,
+                name,
+                &path,
+                None,
+                &[],
+                &relations,
+                &HashMap::new(),
+            )
+            .unwrap();
+        }
+
+        let mut rows = db.fetch_all_dynamic("My Projects").unwrap();
+        db.expand_relations(&config, "My Projects", &mut rows).unwrap();
+        let p1 = rows.iter().find(|r| r["name"] == "P1").unwrap();
+        assert_eq!(p1["select"]["group"], "B");
+        let p2 = rows.iter().find(|r| r["name"] == "P2").unwrap();
+        assert!(p2["select"].is_null());
+
+        let referrers = db.fetch_referrers(&config, "Order", &id).unwrap();
+        let projects = referrers["My Projects"].as_array().unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0]["name"], "P1");
+    }
+
+    #[test]
+    fn test_fetch_dynamic_filters_sorts_and_paginates() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let db = SqliteManager::new(":memory:").unwrap();
+        db.init_schema(&config).unwrap();
+
+        for (name, total) in [
+            ("Alpha", 10.0),
+            ("Beta 100%", 250.0),
+            ("Gamma", 90.0),
+            ("Delta", 400.0),
+        ] {
+            let mut aggregations = HashMap::new();
+            aggregations.insert("total sum".to_string(), AggregationValue::Number(total));
+            let path = format!("/p/{}", name);
+            db.upsert_island(
+                "My Projects",
+                name,
+                name,
+                &path,
+                None,
+                &[],
+                &HashMap::new(),
+                &aggregations,
+            )
+            .unwrap();
+        }
+
+        let filter = |field: &str, op, value| DataFilter {
+            field: field.to_string(),
+            op,
+            value,
+        };
+        let range = vec![
+            filter("total sum", FilterOp::Gte, SqlValue::Integer(50)),
+            filter("total sum", FilterOp::Lte, SqlValue::Integer(300)),
+        ];
+        let query = DataQuery {
+            filters: range.clone(),
+            sort: vec![SortKey {
+                field: "total sum".to_string(),
+                descending: true,
+            }],
+            limit: Some(1),
+            offset: Some(1),
+        };
+        let rows = db.fetch_dynamic("My Projects", &query).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["name"], "Gamma");
+        assert_eq!(db.count_dynamic("My Projects", &range).unwrap(), 2);
+
+        // Bez sortiranja stranice slijede `id`, ne redoslijed upisa.
+        let pages: Vec<JsonValue> = (0..4)
+            .map(|offset| {
+                let page = DataQuery {
+                    limit: Some(1),
+                    offset: Some(offset),
+                    ..DataQuery::default()
+                };
+                db.fetch_dynamic("My Projects", &page).unwrap()[0]["name"].clone()
+            })
+            .collect();
+        assert_eq!(pages, ["Alpha", "Beta 100%", "Delta", "Gamma"]);
+
+        // `%` u vrijednosti se tretira doslovno, ne kao LIKE wildcard.
+        let contains = vec![filter(
+            "name",
+            FilterOp::Contains,
+            SqlValue::Text("0%".to_string()),
+        )];
+        assert_eq!(db.count_dynamic("My Projects", &contains).unwrap(), 1);
+
+        let unknown = DataQuery {
+            filters: vec![filter("nope", FilterOp::Eq, SqlValue::Null)],
+            ..DataQuery::default()
+        };
+        assert!(db.fetch_dynamic("My Projects", &unknown).is_err());
+    }
+
+    #[test]
+    fn test_pending_actions_publish_events() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let db = SqliteManager::new(":memory:").unwrap();
+        db.init_schema(&config).unwrap();
+        let mut rx = db.events().subscribe();
+
+        db.check_or_create_pending("Order", "group", "Acme", "{}").unwrap();
+        let Ok(ValterEvent::PendingActionCreated {
+            action_id, value, ..
+        }) = rx.try_recv()
+        else {
+            panic!("expected PendingActionCreated");
+        };
+        assert_eq!(value, "Acme");
+
+        // Već postojeća pending akcija ne stvara novi događaj.
+        db.check_or_create_pending("Order", "group", "Acme", "{}").unwrap();
+        assert!(rx.try_recv().is_err());
+
+        db.reject_pending_action(&action_id).unwrap();
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            ValterEvent::PendingActionResolved {
+                action_id,
+                status: "Rejected".to_string()
+            }
+        );
+    }
 }
@@ -1,5 +1,7 @@
+use crate::validation;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 
 // ========================================================================= //
@@ -25,12 +27,22 @@ pub struct GlobalConfig {
     pub locale: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// Prozor (ms) u kojem se događaji s diska skupljaju prije obrade.
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+    /// Broj paralelnih radnika kod skeniranja; bez vrijednosti broj jezgri.
+    #[serde(default)]
+    pub scan_workers: Option<usize>,
 }
 
 fn default_port() -> u16 {
     8000
 }
 
+fn default_debounce_ms() -> u64 {
+    300
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CloudDefinition {
     pub name: String,
@@ -42,13 +54,30 @@ pub struct CloudDefinition {
 pub struct CloudField {
     pub key: String,
     #[serde(rename = "type")]
-    pub field_type: String,
+    pub field_type: FieldType,
     #[serde(default)]
     pub required: bool,
     #[serde(default)]
     pub options: Option<Vec<String>>,
 }
 
+/// Tipovi polja u Cloud tablicama. Validacija i pretvorba su u `fields.rs`.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FieldType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Date,
+    Datetime,
+    Select,
+    MultiSelect,
+    Email,
+    Url,
+    Currency,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct IslandDefinition {
     pub name: String,
@@ -58,12 +87,78 @@ pub struct IslandDefinition {
     pub relations: Vec<RelationRule>,
     #[serde(default)]
     pub aggregations: Vec<AggregationRule>,
+    #[serde(default)]
+    pub derived: Vec<DerivedMetric>,
+    /// Odakle dolazi stabilni ID otoka (neovisan o `name`).
+    #[serde(default)]
+    pub identity: IslandIdentity,
+    /// Što napraviti s retkom kad folder ili meta fajl nestane s diska.
+    #[serde(default)]
+    pub on_missing: MissingPolicy,
+    /// Najveća dubina foldera otoka ispod statičkog dijela `root_path` (za `**`).
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Polja meta fajla koja moraju imati zadanu vrijednost (npr. `type: project`).
+    /// Lista kao vrijednost znači "bilo koja od".
+    #[serde(default, rename = "match")]
+    pub match_fields: BTreeMap<String, serde_yaml::Value>,
+    /// Glob uzorci nad (kanonskom) putanjom foldera, npr. `**/clients/*`.
+    #[serde(default)]
+    pub match_path: Vec<String>,
+    /// Dodatna polja iz meta fajla (`description`, `created_at`...), tipizirana
+    /// i validirana kao polja Cloud tablica.
+    #[serde(default)]
+    pub fields: Vec<CloudField>,
+    /// Ključevi meta fajla koji nisu ni polje ni relacija spremaju se kao JSON
+    /// objekt u stupac `extra`.
+    #[serde(default)]
+    pub capture_extra: bool,
+}
+
+/// Stupac otoka za nepoznate ključeve meta fajla (`capture_extra`).
+pub const EXTRA_COLUMN: &str = "extra";
+
+/// Ugrađeni stupac statusa otoka. Može se deklarirati u `fields` (npr. kao
+/// `select`), a inače se iz meta fajla prepisuje kao tekst.
+pub const STATUS_COLUMN: &str = "status";
+
+/// Ključ meta fajla s listom tagova; tagovi se spremaju u tablicu `tags`.
+pub const TAGS_KEY: &str = "tags";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingPolicy {
+    #[default]
+    Delete,
+    /// Redak ostaje (npr. za povijesne izvještaje) sa `status = 'Missing'`.
+    Archive,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IslandIdentity {
+    /// `valter_id` u meta fajlu; upisuje se pri prvom skeniranju ako ne postoji.
+    #[default]
+    File,
+    /// ID izveden iz putanje foldera (meta fajl se ne dira, ali premještanje mijenja ID).
+    Path,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct RelationRule {
     pub field: String,
     pub target_cloud: String,
+    /// `many` za liste referenci (npr. `team: [Arnold S., Sarah C.]`).
+    #[serde(default)]
+    pub cardinality: Cardinality,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Cardinality {
+    #[default]
+    One,
+    Many,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -74,6 +169,34 @@ pub struct AggregationRule {
     pub logic: AggregationLogic,
     #[serde(default)]
     pub filter: Option<String>,
+    /// Polje s datumom po kojem `first`/`last` biraju vrijednost.
+    #[serde(default)]
+    pub date_field: Option<String>,
+    /// Nadjačava prepoznavanje formata po ekstenziji datoteke.
+    #[serde(default)]
+    pub format: Option<DocumentFormat>,
+    /// Uključuje datoteke ugniježđenih otoka (pod-projekata). Po defaultu se
+    /// preskaču kako se isti računi ne bi brojali i u roditelju i u djetetu.
+    /// Podfolder koji nije otok (npr. izvan `root_path`) pripada roditelju.
+    #[serde(default)]
+    pub include_children: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    Yaml,
+    /// YAML front-matter na početku Markdown datoteke.
+    Markdown,
+    Json,
+    Toml,
+}
+
+/// Metrika izračunata iz drugih agregacija, npr. `invoiced_total - paid_total`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DerivedMetric {
+    pub name: String,
+    pub expression: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -82,15 +205,30 @@ pub enum AggregationLogic {
     Sum,
     Count,
     Average,
+    Min,
+    Max,
+    Median,
+    #[serde(rename = "count_distinct")]
+    CountDistinct,
+    First,
+    Last,
 }
 
 impl Config {
+    /// Učitava i validira konfiguraciju. Greška sadrži sve pronađene probleme, svaki s linijom.
     pub fn load(path: &str) -> Result<Self> {
         let content = fs::read_to_string(path)?;
         let config: Config = serde_yaml::from_str(&content)?;
 
-        if config.clouds.is_empty() {
-            anyhow::bail!("Configuration must define at least one CLOUD.");
+        let diagnostics = validation::validate(&config, &content);
+        if !diagnostics.is_empty() {
+            let report: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+            anyhow::bail!(
+                "Invalid configuration {} ({} problem(s)):\n{}",
+                path,
+                diagnostics.len(),
+                report.join("\n")
+            );
         }
 
         Ok(config)
@@ -0,0 +1,70 @@
+// core/src/debounce.rs
+
+//! Sloj između `Watcher`a i `EventProcessor`a. Spremanje u editoru ili
+//! `git checkout` generiraju desetke sirovih notify događaja; ovdje ih skupljamo
+//! tijekom prozora (`GLOBAL.debounce_ms`) i predajemo procesoru odjednom, a on
+//! svaki zahvaćeni otok obrađuje samo jednom.
+
+use notify::Event;
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub struct Debouncer {
+    window: Duration,
+    pending: Vec<Event>,
+    deadline: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Vec::new(),
+            deadline: None,
+        }
+    }
+
+    /// Dodaje događaj u tekući prozor. Prozor se mjeri od prvog događaja, pa
+    /// neprekidan niz promjena ipak ne odgađa obradu beskonačno.
+    pub fn push(&mut self, event: Event) {
+        if self.deadline.is_none() {
+            self.deadline = Some(Instant::now() + self.window);
+        }
+        self.pending.push(event);
+    }
+
+    /// Kada treba isprazniti prozor; `None` ako nema ništa na čekanju.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Vraća skupljene događaje i zatvara prozor.
+    pub fn take(&mut self) -> Vec<Event> {
+        self.deadline = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::EventKind;
+
+    #[tokio::test]
+    async fn test_window_starts_at_first_event() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(50));
+        assert!(debouncer.deadline().is_none());
+
+        debouncer.push(Event::new(EventKind::Any));
+        let deadline = debouncer.deadline().unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        debouncer.push(Event::new(EventKind::Any));
+        assert_eq!(debouncer.deadline(), Some(deadline));
+
+        tokio::time::sleep_until(deadline).await;
+        assert_eq!(debouncer.take().len(), 2);
+        assert!(debouncer.deadline().is_none());
+        assert!(debouncer.take().is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+// core/src/derived.rs
+
+//! Izvedene metrike otoka (`derived` u konfiguraciji), npr. `outstanding = invoiced_total - paid_total`.
+//!
+//! Izraz smije koristiti brojeve, `+ - * /`, zagrade i imena drugih agregacija
+//! ili izvedenih metrika. Redoslijed evaluacije se određuje topološki, a ciklusi
+//! se odbijaju već pri učitavanju konfiguracije.
+
+use crate::aggregator::AggregationValue;
+use crate::config::DerivedMetric;
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DerivedExpr {
+    Number(f64),
+    Ref(String),
+    Neg(Box<DerivedExpr>),
+    Binary(Box<DerivedExpr>, char, Box<DerivedExpr>),
+}
+
+impl DerivedExpr {
+    pub fn parse(input: &str) -> Result<Self> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut parser = Parser { chars, pos: 0 };
+        let expr = parser.parse_sum()?;
+        if parser.peek().is_some() {
+            bail!(
+                "Unexpected '{}' in expression '{}'",
+                parser.chars[parser.pos],
+                input
+            );
+        }
+        Ok(expr)
+    }
+
+    /// Imena metrika na koje izraz referira.
+    pub fn references(&self) -> HashSet<String> {
+        let mut refs = HashSet::new();
+        self.collect_refs(&mut refs);
+        refs
+    }
+
+    fn collect_refs(&self, refs: &mut HashSet<String>) {
+        match self {
+            DerivedExpr::Number(_) => {}
+            DerivedExpr::Ref(name) => {
+                refs.insert(name.clone());
+            }
+            DerivedExpr::Neg(inner) => inner.collect_refs(refs),
+            DerivedExpr::Binary(a, _, b) => {
+                a.collect_refs(refs);
+                b.collect_refs(refs);
+            }
+        }
+    }
+
+    /// Evaluira izraz. Nenumerička vrijednost ili dijeljenje s nulom daju `None`.
+    pub fn eval(&self, values: &HashMap<String, AggregationValue>) -> Option<f64> {
+        match self {
+            DerivedExpr::Number(n) => Some(*n),
+            DerivedExpr::Ref(name) => match values.get(name)? {
+                AggregationValue::Number(n) => Some(*n),
+                _ => None,
+            },
+            DerivedExpr::Neg(inner) => inner.eval(values).map(|v| -v),
+            DerivedExpr::Binary(a, op, b) => {
+                let (a, b) = (a.eval(values)?, b.eval(values)?);
+                match op {
+                    '+' => Some(a + b),
+                    '-' => Some(a - b),
+                    '*' => Some(a * b),
+                    '/' if b != 0.0 => Some(a / b),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Vraća izvedene metrike poredane tako da svaka dolazi nakon onih o kojima ovisi.
+/// `known` su imena sirovih agregacija dostupnih prije evaluacije.
+pub fn evaluation_order<'a>(
+    metrics: &'a [DerivedMetric],
+    known: &[&str],
+) -> Result<Vec<(&'a DerivedMetric, DerivedExpr)>> {
+    let mut pending: Vec<(&DerivedMetric, DerivedExpr, HashSet<String>)> = Vec::new();
+    for metric in metrics {
+        let expr = DerivedExpr::parse(&metric.expression)?;
+        let refs = expr.references();
+        for r in &refs {
+            if !known.contains(&r.as_str()) && !metrics.iter().any(|m| &m.name == r) {
+                bail!(
+                    "Derived metric '{}' references unknown metric '{}'",
+                    metric.name,
+                    r
+                );
+            }
+        }
+        pending.push((metric, expr, refs));
+    }
+
+    let mut resolved: HashSet<String> = known.iter().map(|s| s.to_string()).collect();
+    let mut ordered = Vec::new();
+    while !pending.is_empty() {
+        let Some(idx) = pending.iter().position(|(_, _, refs)| refs.is_subset(&resolved)) else {
+            let names: Vec<&str> = pending.iter().map(|(m, _, _)| m.name.as_str()).collect();
+            bail!(
+                "Cycle detected between derived metrics: {}",
+                names.join(", ")
+            );
+        };
+        let (metric, expr, _) = pending.remove(idx);
+        resolved.insert(metric.name.clone());
+        ordered.push((metric, expr));
+    }
+    Ok(ordered)
+}
+
+// ========================================================================= //
+// PARSER (rekurzivni spust: sum -> product -> unary -> atom)
+// ========================================================================= //
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    /// Sljedeći znak koji nije razmak.
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn raw(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse_sum(&mut self) -> Result<DerivedExpr> {
+        let mut lhs = self.parse_product()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.parse_product()?;
+            lhs = DerivedExpr::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_product(&mut self) -> Result<DerivedExpr> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.parse_unary()?;
+            lhs = DerivedExpr::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<DerivedExpr> {
+        if self.peek() == Some('-') {
+            self.pos += 1;
+            return Ok(DerivedExpr::Neg(Box::new(self.parse_unary()?)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<DerivedExpr> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.parse_sum()?;
+                if self.peek() != Some(')') {
+                    bail!("Expected ')' in expression");
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.raw().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let s: String = self.chars[start..self.pos].iter().collect();
+                match s.parse::<f64>() {
+                    Ok(n) => Ok(DerivedExpr::Number(n)),
+                    Err(_) => bail!("Invalid number '{}' in expression", s),
+                }
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let start = self.pos;
+                while self.raw().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    self.pos += 1;
+                }
+                Ok(DerivedExpr::Ref(
+                    self.chars[start..self.pos].iter().collect(),
+                ))
+            }
+            Some(c) => bail!("Unexpected '{}' in expression", c),
+            None => bail!("Unexpected end of expression"),
+        }
+    }
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric(name: &str, expression: &str) -> DerivedMetric {
+        DerivedMetric {
+            name: name.to_string(),
+            expression: expression.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_eval_respects_precedence_and_references() {
+        let mut values = HashMap::new();
+        values.insert("invoiced".to_string(), AggregationValue::Number(1000.0));
+        values.insert("paid".to_string(), AggregationValue::Number(400.0));
+
+        let expr = DerivedExpr::parse("(invoiced - paid) / invoiced * 100").unwrap();
+        assert_eq!(expr.eval(&values), Some(60.0));
+        assert_eq!(DerivedExpr::parse("paid / 0").unwrap().eval(&values), None);
+        assert!(DerivedExpr::parse("paid +").is_err());
+        assert!(DerivedExpr::parse("paid 2").is_err());
+    }
+
+    #[test]
+    fn test_evaluation_order_and_cycles() {
+        let metrics = vec![
+            metric("ratio", "outstanding / invoiced"),
+            metric("outstanding", "invoiced - paid"),
+        ];
+        let order = evaluation_order(&metrics, &["invoiced", "paid"]).unwrap();
+        let names: Vec<&str> = order.iter().map(|(m, _)| m.name.as_str()).collect();
+        assert_eq!(names, vec!["outstanding", "ratio"]);
+
+        let cyclic = vec![metric("a", "b + 1"), metric("b", "a * 2")];
+        let err = evaluation_order(&cyclic, &[]).unwrap_err().to_string();
+        assert!(err.contains("Cycle detected"));
+
+        let unknown = vec![metric("a", "missing + 1")];
+        assert!(evaluation_order(&unknown, &[]).is_err());
+    }
+}
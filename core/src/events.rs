@@ -0,0 +1,66 @@
+// core/src/events.rs
+
+//! Interna sabirnica događaja (broadcast). `EventProcessor` i `SqliteManager`
+//! objavljuju promjene, a GraphQL pretplate (`/ws`) ih prosljeđuju klijentima.
+//!
+//! Sabirnica živi dulje od jedne konfiguracije, pa pretplatnici dobiju i
+//! `ConfigReloaded` kad se API ponovno pokreće.
+
+use tokio::sync::broadcast;
+
+/// Koliko događaja spori pretplatnik smije zaostati prije nego počne gubiti stare.
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValterEvent {
+    IslandUpserted {
+        island_type: String,
+        id: String,
+        name: String,
+        path: String,
+    },
+    /// Otok je nestao s diska. `archived` znači da je redak zadržan sa `status = 'Missing'`.
+    IslandRemoved {
+        island_type: String,
+        id: String,
+        archived: bool,
+    },
+    PendingActionCreated {
+        action_id: String,
+        target_table: String,
+        value: String,
+    },
+    /// `status` je konačno stanje akcije: `Resolved` ili `Rejected`.
+    PendingActionResolved {
+        action_id: String,
+        status: String,
+    },
+    ConfigReloaded,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<ValterEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        Self { tx }
+    }
+
+    /// Objavljuje događaj. Ako nema pretplatnika, događaj se jednostavno odbacuje.
+    pub fn publish(&self, event: ValterEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ValterEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,125 @@
+// core/src/field_path.rs
+
+//! Putanje do polja unutar YAML dokumenta: `client.billing.rate`,
+//! `items[0].total` ili `items[*].total` (grananje preko svih elemenata liste).
+
+use anyhow::{bail, Result};
+use serde_yaml::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldPath {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+impl FieldPath {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+
+        for part in input.split('.') {
+            let (key, mut rest) = match part.find('[') {
+                Some(i) => (&part[..i], &part[i..]),
+                None => (part, ""),
+            };
+            if key.is_empty() && (segments.is_empty() || rest.is_empty()) {
+                bail!("Empty segment in field path '{}'", input);
+            }
+            if !key.is_empty() {
+                segments.push(Segment::Key(key.to_string()));
+            }
+
+            while !rest.is_empty() {
+                let Some(close) = rest.find(']') else {
+                    bail!("Unclosed '[' in field path '{}'", input);
+                };
+                if !rest.starts_with('[') {
+                    bail!("Unexpected '{}' in field path '{}'", rest, input);
+                }
+                let inner = &rest[1..close];
+                segments.push(match inner {
+                    "*" => Segment::Wildcard,
+                    _ => match inner.parse::<usize>() {
+                        Ok(i) => Segment::Index(i),
+                        Err(_) => bail!("Invalid index '[{}]' in field path '{}'", inner, input),
+                    },
+                });
+                rest = &rest[close + 1..];
+            }
+        }
+
+        Ok(Self {
+            raw: input.to_string(),
+            segments,
+        })
+    }
+
+    /// Sve vrijednosti na koje putanja pokazuje. Bez `[*]` rezultat ima najviše jedan element.
+    pub fn resolve<'a>(&self, doc: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![doc];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|v| -> Vec<&'a Value> {
+                    match (segment, v) {
+                        (Segment::Key(k), Value::Mapping(_)) => {
+                            v.get(k.as_str()).into_iter().collect()
+                        }
+                        (Segment::Index(i), Value::Sequence(seq)) => {
+                            seq.get(*i).into_iter().collect()
+                        }
+                        (Segment::Wildcard, Value::Sequence(seq)) => seq.iter().collect(),
+                        _ => Vec::new(),
+                    }
+                })
+                .collect();
+        }
+        current
+    }
+
+    /// Sadrži li putanja `[*]`, tj. može li dati više vrijednosti po dokumentu.
+    pub fn fans_out(&self) -> bool {
+        self.segments.contains(&Segment::Wildcard)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_nested_and_wildcard_paths() {
+        let doc: Value = serde_yaml::from_str(
+            "client:\n  billing:\n    rate: 80\nitems:\n  - total: 100\n  - total: 250\n  - name: x",
+        )
+        .unwrap();
+
+        let rate = FieldPath::parse("client.billing.rate").unwrap();
+        assert_eq!(rate.resolve(&doc), vec![&Value::from(80)]);
+
+        let second = FieldPath::parse("items[1].total").unwrap();
+        assert_eq!(second.resolve(&doc), vec![&Value::from(250)]);
+
+        let totals = FieldPath::parse("items[*].total").unwrap();
+        assert_eq!(
+            totals.resolve(&doc),
+            vec![&Value::from(100), &Value::from(250)]
+        );
+
+        assert!(FieldPath::parse("client.missing").unwrap().resolve(&doc).is_empty());
+        assert!(FieldPath::parse("items[").is_err());
+        assert!(FieldPath::parse("items[x]").is_err());
+        assert!(FieldPath::parse("a..b").is_err());
+    }
+}
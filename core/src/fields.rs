@@ -0,0 +1,303 @@
+// core/src/fields.rs
+
+//! Validacija i pretvorba vrijednosti za tipizirana polja (`CloudField`).
+//!
+//! Ulaz dolazi kao JSON (GraphQL mutacije, pending akcije), a izlaz je
+//! vrijednost spremna za vezanje u SQLite upit.
+
+use crate::config::{CloudField, FieldType};
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use rusqlite::types::Value as SqlValue;
+use serde_json::{Map, Value as JsonValue};
+use std::fmt;
+
+/// Zapis ne odgovara definiciji polja. Poseban tip kako bi se razlikovao od
+/// I/O ili SQL grešaka (npr. u `scan_errors`).
+#[derive(Debug)]
+pub struct ValidationError(pub String);
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Validation failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl FieldType {
+    /// SQLite tip stupca za ovaj tip polja.
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            FieldType::Number | FieldType::Currency => "REAL",
+            FieldType::Integer | FieldType::Boolean => "INTEGER",
+            _ => "TEXT",
+        }
+    }
+
+    /// Podržava li tip listu dopuštenih vrijednosti (`options`).
+    pub fn has_options(&self) -> bool {
+        matches!(self, FieldType::Select | FieldType::MultiSelect)
+    }
+}
+
+/// Pretvara jednu vrijednost u SQL vrijednost prema tipu polja.
+/// `null` (ili prazan string) je dopušten samo za polja koja nisu `required`.
+pub fn coerce(field: &CloudField, raw: &JsonValue) -> Result<SqlValue> {
+    let is_empty = match raw {
+        JsonValue::Null => true,
+        JsonValue::String(s) => s.trim().is_empty(),
+        JsonValue::Array(a) => a.is_empty(),
+        _ => false,
+    };
+    if is_empty {
+        if field.required {
+            bail!("required field is missing");
+        }
+        return Ok(SqlValue::Null);
+    }
+
+    let text = match raw {
+        JsonValue::String(s) => s.trim().to_string(),
+        other => other.to_string(),
+    };
+
+    let value = match field.field_type {
+        FieldType::String => SqlValue::Text(text),
+        FieldType::Number => SqlValue::Real(parse_number(raw, &text)?),
+        FieldType::Currency => {
+            // Iznosi se spremaju zaokruženi na dvije decimale.
+            let amount = parse_number(raw, &text.replace([',', ' '], ""))?;
+            SqlValue::Real((amount * 100.0).round() / 100.0)
+        }
+        FieldType::Integer => {
+            let n = parse_number(raw, &text)?;
+            if n.fract() != 0.0 {
+                bail!("'{}' is not an integer", text);
+            }
+            SqlValue::Integer(n as i64)
+        }
+        FieldType::Boolean => match (raw, text.to_lowercase().as_str()) {
+            (JsonValue::Bool(b), _) => SqlValue::Integer(i64::from(*b)),
+            (_, "true" | "yes" | "1") => SqlValue::Integer(1),
+            (_, "false" | "no" | "0") => SqlValue::Integer(0),
+            _ => bail!("'{}' is not a boolean", text),
+        },
+        FieldType::Date => match NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+            Ok(d) => SqlValue::Text(d.format("%Y-%m-%d").to_string()),
+            Err(_) => bail!("'{}' is not a date (expected YYYY-MM-DD)", text),
+        },
+        FieldType::Datetime => {
+            let parsed = DateTime::parse_from_rfc3339(&text)
+                .map(|d| d.naive_utc())
+                .or_else(|_| NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S"))
+                .or_else(|_| NaiveDateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S"));
+            match parsed {
+                Ok(d) => SqlValue::Text(d.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                Err(_) => bail!("'{}' is not a datetime (expected RFC 3339)", text),
+            }
+        }
+        FieldType::Select => {
+            check_option(field, &text)?;
+            SqlValue::Text(text)
+        }
+        FieldType::MultiSelect => {
+            let items: Vec<String> = match raw {
+                JsonValue::Array(items) => items
+                    .iter()
+                    .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                    .collect(),
+                _ => text.split(',').map(|s| s.trim().to_string()).collect(),
+            };
+            for item in &items {
+                check_option(field, item)?;
+            }
+            SqlValue::Text(serde_json::to_string(&items)?)
+        }
+        FieldType::Email => {
+            let valid = text.split_once('@').is_some_and(|(local, domain)| {
+                !local.is_empty()
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && !text.contains(char::is_whitespace)
+            });
+            if !valid {
+                bail!("'{}' is not a valid email address", text);
+            }
+            SqlValue::Text(text)
+        }
+        FieldType::Url => {
+            let host = text.strip_prefix("https://").or_else(|| text.strip_prefix("http://"));
+            if host.is_none_or(|h| h.is_empty() || h.contains(char::is_whitespace)) {
+                bail!("'{}' is not a valid http(s) URL", text);
+            }
+            SqlValue::Text(text)
+        }
+    };
+    Ok(value)
+}
+
+/// Validira cijeli zapis prema definiciji polja i vraća parove (stupac, vrijednost).
+///
+/// Kod djelomičnog ažuriranja (`partial`) provjeravaju se samo poslana polja.
+/// Sve greške se skupljaju u jednu poruku kako bi klijent vidio sve odjednom.
+pub fn validate_record(
+    fields: &[CloudField],
+    data: &Map<String, JsonValue>,
+    partial: bool,
+) -> Result<Vec<(String, SqlValue)>> {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+
+    for key in data.keys() {
+        if !fields.iter().any(|f| &f.key == key) {
+            errors.push(format!("{}: unknown field", key));
+        }
+    }
+
+    for field in fields {
+        let raw = match data.get(&field.key) {
+            Some(v) => v,
+            None if partial => continue,
+            None => &JsonValue::Null,
+        };
+        match coerce(field, raw) {
+            Ok(v) => values.push((field.key.clone(), v)),
+            Err(e) => errors.push(format!("{}: {}", field.key, e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(ValidationError(errors.join("; ")).into());
+    }
+    Ok(values)
+}
+
+fn parse_number(raw: &JsonValue, text: &str) -> Result<f64> {
+    match raw.as_f64() {
+        Some(n) => Ok(n),
+        None => match text.parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(n),
+            _ => bail!("'{}' is not a number", text),
+        },
+    }
+}
+
+fn check_option(field: &CloudField, value: &str) -> Result<()> {
+    let options = field.options.as_deref().unwrap_or_default();
+    if !options.iter().any(|o| o == value) {
+        bail!("'{}' is not one of [{}]", value, options.join(", "));
+    }
+    Ok(())
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field(key: &str, field_type: FieldType, required: bool) -> CloudField {
+        CloudField {
+            key: key.to_string(),
+            field_type,
+            required,
+            options: field_type
+                .has_options()
+                .then(|| vec!["Active".to_string(), "Lead".to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_coerce_types() {
+        let cases = [
+            (FieldType::Number, json!("12.5"), SqlValue::Real(12.5)),
+            (
+                FieldType::Currency,
+                json!("1,234.567"),
+                SqlValue::Real(1234.57),
+            ),
+            (FieldType::Integer, json!(7), SqlValue::Integer(7)),
+            (FieldType::Boolean, json!("yes"), SqlValue::Integer(1)),
+            (
+                FieldType::Date,
+                json!("2024-02-29"),
+                SqlValue::Text("2024-02-29".into()),
+            ),
+            (
+                FieldType::Datetime,
+                json!("2024-01-01T10:00:00+02:00"),
+                SqlValue::Text("2024-01-01T08:00:00".into()),
+            ),
+            (
+                FieldType::Select,
+                json!("Lead"),
+                SqlValue::Text("Lead".into()),
+            ),
+            (
+                FieldType::MultiSelect,
+                json!(["Active", "Lead"]),
+                SqlValue::Text(r#"["Active","Lead"]"#.into()),
+            ),
+            (
+                FieldType::Email,
+                json!("a@b.hr"),
+                SqlValue::Text("a@b.hr".into()),
+            ),
+            (
+                FieldType::Url,
+                json!("https://valter.sh"),
+                SqlValue::Text("https://valter.sh".into()),
+            ),
+        ];
+        for (t, raw, expected) in cases {
+            assert_eq!(
+                coerce(&field("f", t, false), &raw).unwrap(),
+                expected,
+                "{:?}",
+                t
+            );
+        }
+
+        let invalid = [
+            (FieldType::Number, json!("abc")),
+            (FieldType::Integer, json!(1.5)),
+            (FieldType::Date, json!("2024-13-01")),
+            (FieldType::Select, json!("Churned")),
+            (FieldType::MultiSelect, json!(["Active", "Nope"])),
+            (FieldType::Email, json!("not-an-email")),
+            (FieldType::Url, json!("ftp://x")),
+        ];
+        for (t, raw) in invalid {
+            assert!(coerce(&field("f", t, false), &raw).is_err(), "{:?}", t);
+        }
+    }
+
+    #[test]
+    fn test_validate_record_reports_all_errors() {
+        let fields = vec![
+            field("name", FieldType::String, true),
+            field("status", FieldType::Select, true),
+            field("email", FieldType::Email, false),
+        ];
+
+        let data = json!({"name": "Acme", "status": "Lead"});
+        let values = validate_record(&fields, data.as_object().unwrap(), false).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[2], ("email".to_string(), SqlValue::Null));
+
+        let data = json!({"email": "x", "extra": 1});
+        let err = validate_record(&fields, data.as_object().unwrap(), false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("extra: unknown field"));
+        assert!(err.contains("name: required field is missing"));
+        assert!(err.contains("status: required field is missing"));
+        assert!(err.contains("email: 'x' is not a valid email address"));
+
+        // Djelomično ažuriranje ne traži polja koja nisu poslana.
+        let data = json!({"status": "Active"});
+        assert!(validate_record(&fields, data.as_object().unwrap(), true).is_ok());
+    }
+}
@@ -0,0 +1,358 @@
+// core/src/filter.rs
+
+//! Mali jezik izraza za `filter` polje na `AggregationRule`.
+//!
+//! Podržava usporedbe (`==`, `!=`, `>`, `>=`, `<`, `<=`), logičke operatore
+//! (`&&`, `||`, `!`) i zagrade, npr. `status == 'unpaid' && amount > 1000`.
+//! Stringovi se uspoređuju leksikografski, pa ISO datumi (`'2024-01-01'`) rade
+//! očekivano. Polja mogu biti ugniježđene putanje (`client.status`,
+//! `items[*].status`); kod grananja je usporedba istinita ako vrijedi za
+//! barem jednu vrijednost.
+
+use crate::field_path::FieldPath;
+use anyhow::{bail, Result};
+use serde_yaml::Value;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Null,
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Field(FieldPath),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    Compare(Operand, CompareOp, Operand),
+    /// Samo ime polja bez usporedbe: istinito ako polje postoji i nije `false`/`null`.
+    Truthy(Operand),
+    Not(Box<FilterExpr>),
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+}
+
+impl FilterExpr {
+    /// Parsira izraz. Greška sadrži poziciju (znak) na kojoj parsiranje nije uspjelo.
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if parser.pos < parser.tokens.len() {
+            let (token, position) = &parser.tokens[parser.pos];
+            bail!(
+                "Unexpected token {:?} at position {} in filter '{}'",
+                token,
+                position,
+                input
+            );
+        }
+        Ok(expr)
+    }
+
+    /// Evaluira izraz nad jednim YAML dokumentom.
+    pub fn matches(&self, doc: &Value) -> bool {
+        match self {
+            FilterExpr::Compare(lhs, op, rhs) => {
+                let rhs = resolve(rhs, doc);
+                resolve(lhs, doc).iter().any(|l| rhs.iter().any(|r| compare(l, *op, r)))
+            }
+            FilterExpr::Truthy(operand) => resolve(operand, doc)
+                .iter()
+                .any(|v| !matches!(v, Value::Null | Value::Bool(false))),
+            FilterExpr::Not(inner) => !inner.matches(doc),
+            FilterExpr::And(a, b) => a.matches(doc) && b.matches(doc),
+            FilterExpr::Or(a, b) => a.matches(doc) || b.matches(doc),
+        }
+    }
+}
+
+/// Vrijednosti operanda; polje koje ne postoji daje `null`.
+fn resolve(operand: &Operand, doc: &Value) -> Vec<Value> {
+    match operand {
+        Operand::Field(path) => {
+            let values: Vec<Value> = path.resolve(doc).into_iter().cloned().collect();
+            if values.is_empty() {
+                vec![Value::Null]
+            } else {
+                values
+            }
+        }
+        Operand::Literal(v) => vec![v.clone()],
+    }
+}
+
+fn as_number(v: &Value) -> Option<f64> {
+    v.as_f64().or_else(|| v.as_i64().map(|i| i as f64))
+}
+
+fn compare(lhs: &Value, op: CompareOp, rhs: &Value) -> bool {
+    let ordering = match (lhs, rhs) {
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => Some(a.as_str().cmp(b.as_str())),
+        _ => match (as_number(lhs), as_number(rhs)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+    };
+
+    match (op, ordering) {
+        (CompareOp::Eq, Some(o)) => o == Ordering::Equal,
+        (CompareOp::Ne, Some(o)) => o != Ordering::Equal,
+        (CompareOp::Ne, None) => true,
+        (CompareOp::Gt, Some(o)) => o == Ordering::Greater,
+        (CompareOp::Ge, Some(o)) => o != Ordering::Less,
+        (CompareOp::Lt, Some(o)) => o == Ordering::Less,
+        (CompareOp::Le, Some(o)) => o != Ordering::Greater,
+        _ => false,
+    }
+}
+
+// ========================================================================= //
+// TOKENIZER
+// ========================================================================= //
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let two: String = chars[i..chars.len().min(i + 2)].iter().collect();
+        let token = match two.as_str() {
+            "==" => Some(Token::Op(CompareOp::Eq)),
+            "!=" => Some(Token::Op(CompareOp::Ne)),
+            ">=" => Some(Token::Op(CompareOp::Ge)),
+            "<=" => Some(Token::Op(CompareOp::Le)),
+            "&&" => Some(Token::And),
+            "||" => Some(Token::Or),
+            _ => None,
+        };
+        if let Some(t) = token {
+            tokens.push((t, start));
+            i += 2;
+            continue;
+        }
+
+        match c {
+            '>' => tokens.push((Token::Op(CompareOp::Gt), start)),
+            '<' => tokens.push((Token::Op(CompareOp::Lt), start)),
+            '!' => tokens.push((Token::Not, start)),
+            '(' => tokens.push((Token::LParen, start)),
+            ')' => tokens.push((Token::RParen, start)),
+            '\'' | '"' => {
+                let quote = c;
+                let mut s = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    s.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    bail!("Unterminated string starting at position {}", start);
+                }
+                tokens.push((Token::Str(s), start));
+            }
+            _ if c.is_ascii_digit()
+                || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) =>
+            {
+                let mut s = String::from(c);
+                while i + 1 < chars.len() && (chars[i + 1].is_ascii_digit() || chars[i + 1] == '.')
+                {
+                    i += 1;
+                    s.push(chars[i]);
+                }
+                match s.parse::<f64>() {
+                    Ok(n) => tokens.push((Token::Num(n), start)),
+                    Err(_) => bail!("Invalid number '{}' at position {}", s, start),
+                }
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut s = String::from(c);
+                while i + 1 < chars.len() && is_ident_char(chars[i + 1]) {
+                    i += 1;
+                    s.push(chars[i]);
+                }
+                let token = match s.as_str() {
+                    "true" => Token::Bool(true),
+                    "false" => Token::Bool(false),
+                    "null" => Token::Null,
+                    _ => Token::Ident(s),
+                };
+                tokens.push((token, start));
+            }
+            _ => bail!("Unexpected character '{}' at position {}", c, start),
+        }
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '[' | ']' | '*')
+}
+
+// ========================================================================= //
+// PARSER (rekurzivni spust: or -> and -> unary -> primary)
+// ========================================================================= //
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        t
+    }
+
+    fn position(&self) -> String {
+        match self.tokens.get(self.pos) {
+            Some((_, p)) => format!("position {}", p),
+            None => "end of input".to_string(),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr> {
+        let mut lhs = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            lhs = FilterExpr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr> {
+        let mut lhs = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let rhs = self.parse_unary()?;
+            lhs = FilterExpr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(FilterExpr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<FilterExpr> {
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.parse_or()?;
+            if self.peek() != Some(&Token::RParen) {
+                bail!("Expected ')' at {}", self.position());
+            }
+            self.pos += 1;
+            return Ok(inner);
+        }
+
+        let lhs = self.parse_operand()?;
+        if let Some(Token::Op(op)) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.parse_operand()?;
+            return Ok(FilterExpr::Compare(lhs, op, rhs));
+        }
+        Ok(FilterExpr::Truthy(lhs))
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(Operand::Field(FieldPath::parse(&name)?)),
+            Some(Token::Str(s)) => Ok(Operand::Literal(Value::String(s))),
+            Some(Token::Num(n)) => Ok(Operand::Literal(Value::Number(n.into()))),
+            Some(Token::Bool(b)) => Ok(Operand::Literal(Value::Bool(b))),
+            Some(Token::Null) => Ok(Operand::Literal(Value::Null)),
+            Some(t) => bail!("Expected field or value at {}, found {:?}", position, t),
+            None => bail!("Expected field or value at {}", position),
+        }
+    }
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_filter_matches_documents() {
+        let invoice = doc("status: unpaid\namount: 1500\ndate: '2024-03-01'");
+
+        assert!(FilterExpr::parse("status == 'unpaid' && amount > 1000")
+            .unwrap()
+            .matches(&invoice));
+        assert!(FilterExpr::parse("date >= '2024-01-01'").unwrap().matches(&invoice));
+        assert!(!FilterExpr::parse("status == \"paid\" || amount < 1000")
+            .unwrap()
+            .matches(&invoice));
+        assert!(FilterExpr::parse("!(amount <= 1500 && missing)").unwrap().matches(&invoice));
+        assert!(FilterExpr::parse("missing == null").unwrap().matches(&invoice));
+
+        let offer = doc("client:\n  status: active\nitems:\n  - total: 10\n  - total: 900");
+        assert!(
+            FilterExpr::parse("client.status == 'active' && items[*].total > 500")
+                .unwrap()
+                .matches(&offer)
+        );
+        assert!(!FilterExpr::parse("items[0].total > 500").unwrap().matches(&offer));
+    }
+
+    #[test]
+    fn test_filter_parse_errors() {
+        assert!(FilterExpr::parse("status == ").is_err());
+        assert!(FilterExpr::parse("status == 'unpaid").is_err());
+        assert!(FilterExpr::parse("(amount > 1").is_err());
+        let err = FilterExpr::parse("amount > 1 1").unwrap_err().to_string();
+        assert!(err.contains("at position 11"), "{}", err);
+        assert!(FilterExpr::parse("amount # 1").is_err());
+    }
+}
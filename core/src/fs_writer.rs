@@ -4,6 +4,9 @@ use std::fs;
 use std::path::Path;
 use tracing::info;
 
+/// Ključ u meta fajlu pod kojim se čuva stabilni ID otoka.
+pub const VALTER_ID_KEY: &str = "valter_id";
+
 pub struct FsWriter;
 
 impl FsWriter {
@@ -72,6 +75,12 @@ impl FsWriter {
             map.insert(Value::String(k), Value::String(v));
         }
 
+        // Stabilni ID otoka (vidi `EventProcessor::island_id`)
+        map.insert(
+            Value::String(VALTER_ID_KEY.to_string()),
+            Value::String(uuid::Uuid::new_v4().to_string()),
+        );
+
         // Dodaj timestamp
         let now = chrono::Local::now().format("%Y-%m-%d").to_string();
         map.insert(Value::String("created_at".to_string()), Value::String(now));
@@ -5,16 +5,29 @@ pub mod api;
 pub mod cloud;
 pub mod config;
 pub mod context_engine;
+pub mod debounce;
+pub mod derived;
+pub mod events;
+pub mod field_path;
+pub mod fields;
+pub mod filter;
 pub mod fs_writer;
 pub mod oracle;
 pub mod processor;
+pub mod root_pattern;
+pub mod scan;
+pub mod typed_schema;
+pub mod validation;
 pub mod watcher;
 
 use anyhow::Result;
 use cloud::SqliteManager;
 use config::{env::EnvConfig, Config};
+use debounce::Debouncer;
+use events::{EventBus, ValterEvent};
 use oracle::ToolGenerator;
 use processor::EventProcessor;
+use scan::ScanJobs;
 use std::fs;
 use std::path::PathBuf;
 use std::process;
@@ -42,6 +55,9 @@ pub async fn run(valter_home: PathBuf, is_dev_mode: bool) -> Result<()> {
     let env_config = Arc::new(EnvConfig::init());
     info!("Environment Config Status: {:?}", env_config.status);
 
+    // Ista sabirnica kroz sve reloadove, da pretplatnici ne izgube vezu s događajima.
+    let events = EventBus::new();
+
     loop {
         let config_path = if is_dev_mode {
             valter_home.join("valter.dev.config")
@@ -66,7 +82,7 @@ pub async fn run(valter_home: PathBuf, is_dev_mode: bool) -> Result<()> {
 
         let db_path = valter_home.join("valter.db");
         let cloud: Arc<SqliteManager> = match SqliteManager::new(db_path.to_str().unwrap()) {
-            Ok(c) => Arc::new(c),
+            Ok(c) => Arc::new(c.with_events(events.clone())),
             Err(e) => {
                 error!("DB Error: {}", e);
                 // ISPRAVAK: Uklonjen nepotreban `.into()`
@@ -82,11 +98,14 @@ pub async fn run(valter_home: PathBuf, is_dev_mode: bool) -> Result<()> {
         let (fs_tx, mut fs_rx) = mpsc::channel(100);
 
         let processor = Arc::new(EventProcessor::new(cloud.clone(), config.clone()));
-        processor.scan_on_startup();
+        // Početno skeniranje teče u pozadini; napredak je vidljiv preko API-ja.
+        let scans = Arc::new(ScanJobs::default());
+        scans.start(processor.clone());
 
         let cloud_clone = cloud.clone();
         let config_clone = config.clone();
         let processor_clone = processor.clone();
+        let scans_clone = scans.clone();
         let env_config_clone = env_config.clone();
         let api_rx = shutdown_tx.subscribe();
         let api_handle = tokio::spawn(async move {
@@ -94,6 +113,7 @@ pub async fn run(valter_home: PathBuf, is_dev_mode: bool) -> Result<()> {
                 cloud_clone,
                 config_clone,
                 processor_clone,
+                scans_clone,
                 env_config_clone,
                 api_rx,
             )
@@ -109,16 +129,22 @@ pub async fn run(valter_home: PathBuf, is_dev_mode: bool) -> Result<()> {
             watch_paths.push(p.to_string_lossy().to_string());
         }
         for island in &config.islands {
-            let clean = island.root_path.replace('*', "");
-            watch_paths.push(clean);
+            if let Ok(pattern) = island.root_pattern() {
+                for root in pattern.watch_roots() {
+                    watch_paths.push(root.to_string_lossy().to_string());
+                }
+            }
         }
+        watch_paths.sort();
         watch_paths.dedup();
 
         let _watcher = Watcher::new(watch_paths, fs_tx)?;
         info!("System Operational. Waiting for events...");
 
+        let mut debouncer = Debouncer::new(Duration::from_millis(config.global.debounce_ms));
         let mut reload = false;
         loop {
+            let flush_at = debouncer.deadline();
             tokio::select! {
                 Some(event) = fs_rx.recv() => {
                     for p in &event.paths {
@@ -128,7 +154,10 @@ pub async fn run(valter_home: PathBuf, is_dev_mode: bool) -> Result<()> {
                         }
                     }
                     if reload { break; }
-                    processor.handle_event(event).await;
+                    debouncer.push(event);
+                }
+                _ = tokio::time::sleep_until(flush_at.unwrap_or_else(tokio::time::Instant::now)), if flush_at.is_some() => {
+                    processor.handle_events(debouncer.take()).await;
                 }
                 _ = tokio::signal::ctrl_c() => {
                     info!("Ctrl-C received. Shutting down...");
@@ -141,6 +170,8 @@ pub async fn run(valter_home: PathBuf, is_dev_mode: bool) -> Result<()> {
 
         if reload {
             info!("Shutting down services for reload...");
+            scans.cancel_running();
+            events.publish(ValterEvent::ConfigReloaded);
             let _ = shutdown_tx.send(());
             let _ = api_handle.await;
             info!("Reloading main loop.");
@@ -21,6 +21,20 @@ enum Commands {
     Start,
     Stop,
     Run,
+    /// Rad s konfiguracijom (`valter config check`).
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Validira konfiguraciju i ispisuje sve probleme s linijom i stupcem.
+    Check {
+        /// Putanja do konfiguracije (zadano: ~/.valter/valter.config)
+        path: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
@@ -37,6 +51,14 @@ async fn main() -> Result<()> {
 
     let command = cli.command.unwrap_or(Commands::Run);
 
+    if let Commands::Config {
+        action: ConfigCommands::Check { path },
+    } = command
+    {
+        let home = env::var("VALTER_HOME").map(PathBuf::from).unwrap_or(default_prod_home);
+        let path = path.unwrap_or_else(|| home.join("valter.config"));
+        return check_config(&path);
+    }
     if let Commands::Stop = command {
         let pid_file = default_prod_home.join("valter.pid");
         return stop_daemon(&pid_file);
@@ -81,6 +103,19 @@ async fn main() -> Result<()> {
     valter_core::run(valter_home, is_dev_mode).await
 }
 
+fn check_config(path: &Path) -> Result<()> {
+    let diagnostics = valter_core::validation::check_file(path)?;
+    if diagnostics.is_empty() {
+        println!("✅ {:?} is valid.", path);
+        return Ok(());
+    }
+    for d in &diagnostics {
+        println!("{}:{}", path.display(), d);
+    }
+    println!("❌ {} problem(s) found.", diagnostics.len());
+    process::exit(1);
+}
+
 fn is_daemon_running(pid_path: &Path) -> bool {
     if !pid_path.exists() {
         return false;
@@ -36,38 +36,41 @@ impl ToolGenerator {
         // 2. Generiraj alate za ISLANDS (Projekti i Agregacije)
         for island in &config.islands {
             for agg in &island.aggregations {
-                let tool_name = format!(
-                    "get_{}_{}",
-                    island.name.to_lowercase(),
-                    agg.name.to_lowercase()
-                );
                 // FIX: Koristimo {:?} za ispis Enuma (Sum, Count...)
-                let description = format!(
-                    "Izračunaj '{}' ({:?}) za {}.",
-                    agg.name, agg.logic, island.name
-                );
+                let logic = format!("{:?}", agg.logic);
+                tools.push(island_metric_tool(&island.name, &agg.name, &logic));
+            }
 
-                let tool = json!({
-                    "type": "function",
-                    "function": {
-                        "name": tool_name,
-                        "description": description,
-                        "parameters": {
-                            "type": "object",
-                            "properties": {
-                                "project_name": {
-                                    "type": "string",
-                                    "description": "Ime projekta (npr. 'Project Phoenix')"
-                                }
-                            },
-                            "required": ["project_name"]
-                        }
-                    }
-                });
-                tools.push(tool);
+            for metric in &island.derived {
+                tools.push(island_metric_tool(
+                    &island.name,
+                    &metric.name,
+                    &metric.expression,
+                ));
             }
         }
 
         Ok(json!(tools))
     }
 }
+
+/// Alat koji vraća jednu metriku otoka (agregaciju ili izvedenu metriku) po imenu projekta.
+fn island_metric_tool(island: &str, metric: &str, detail: &str) -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": format!("get_{}_{}", island.to_lowercase(), metric.to_lowercase()),
+            "description": format!("Izračunaj '{}' ({}) za {}.", metric, detail, island),
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "project_name": {
+                        "type": "string",
+                        "description": "Ime projekta (npr. 'Project Phoenix')"
+                    }
+                },
+                "required": ["project_name"]
+            }
+        }
+    })
+}
@@ -1,124 +1,462 @@
 use crate::aggregator::Aggregator;
-use crate::cloud::{EntityStatus, SqliteManager};
-use crate::config::{Config, IslandDefinition};
-use notify::Event;
+use crate::cloud::{EntityStatus, ScanError, ScanErrorKind, SqliteManager};
+use crate::config::{
+    Cardinality, Config, IslandDefinition, IslandIdentity, MissingPolicy, RelationRule,
+    EXTRA_COLUMN, STATUS_COLUMN, TAGS_KEY,
+};
+use crate::events::ValterEvent;
+use crate::field_path::FieldPath;
+use crate::fields::{self, ValidationError};
+use crate::fs_writer::{FsWriter, VALTER_ID_KEY};
+use crate::root_pattern::{canonical_path, RootPattern};
+use crate::scan::ScanJob;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind};
+use rusqlite::types::Value as SqlValue;
 use serde_json::json;
 use serde_yaml::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 use walkdir::WalkDir;
 
 pub struct EventProcessor {
     cloud: Arc<SqliteManager>,
     config: Arc<Config>,
+    /// Kompilirani `root_path` uzorci po imenu tipa otoka.
+    roots: HashMap<String, RootPattern>,
+    claim_lock: Mutex<()>,
 }
 
 impl EventProcessor {
     pub fn new(cloud: Arc<SqliteManager>, config: Arc<Config>) -> Self {
-        Self { cloud, config }
+        let mut roots = HashMap::new();
+        for island in &config.islands {
+            match island.root_pattern() {
+                Ok(pattern) => {
+                    roots.insert(island.name.clone(), pattern);
+                }
+                Err(e) => error!("Island '{}' disabled: {}", island.name, e),
+            }
+        }
+        Self {
+            cloud,
+            config,
+            roots,
+            claim_lock: Mutex::new(()),
+        }
+    }
+
+    /// Je li `dir` folder otoka tipa `island`?
+    fn is_island_dir(&self, island: &IslandDefinition, dir: &Path) -> bool {
+        self.roots.get(&island.name).is_some_and(|r| r.matches_dir(dir))
     }
 
     /// Skenira sve definirane lokacije iz Configa prilikom pokretanja
     pub fn scan_on_startup(&self) {
+        self.scan(&ScanJob::new());
+    }
+
+    /// Skenira sve otoke paralelno (ograničen broj radnika) i bilježi napredak
+    /// u `job`. Otkazani posao prestaje prije sljedećeg otoka i preskače usklađivanje.
+    pub fn scan(&self, job: &ScanJob) {
         info!("🔍 Initial Scan: Starting...");
+        let mut found = Vec::new();
         for island_def in &self.config.islands {
-            // Uklanjamo wildcard (*) da dobijemo base path
-            let base_path_str = island_def.root_path.replace('*', "");
-            let base_path = Path::new(&base_path_str);
-
-            if !base_path.exists() {
+            let Some(root) = self.roots.get(&island_def.name) else {
+                continue;
+            };
+            if !root.watch_roots().iter().any(|p| p.exists()) {
                 warn!(
                     "⚠️  Path not found: {:?} (Skipping scan for '{}')",
-                    base_path, island_def.name
+                    island_def.root_path, island_def.name
                 );
                 continue;
             }
 
             info!(
                 "📂 Scanning Island Type '{}' in: {:?}",
-                island_def.name, base_path
+                island_def.name, island_def.root_path
             );
 
-            for entry in WalkDir::new(base_path).into_iter().filter_map(|e| e.ok()) {
-                if let Some(fname) = entry.file_name().to_str() {
-                    // Provjeravamo odgovara li ime fajla definiciji (npr. meta.yaml)
-                    if fname == island_def.meta_file {
-                        if let Err(e) = self.process_metadata(entry.path(), island_def) {
-                            error!("❌ Failed to process {:?}: {}", entry.path(), e);
+            for dir in root.discover() {
+                let meta_path = dir.join(&island_def.meta_file);
+                if meta_path.is_file() && self.is_classified_as(&meta_path, island_def) {
+                    found.push((meta_path, island_def));
+                }
+            }
+        }
+
+        job.add_found(found.len());
+
+        // Razina po razina dubine: roditelji prije djece, da pod-projekt odmah
+        // nađe `parent_id`. Unutar razine otoci se obrađuju paralelno.
+        let mut levels: BTreeMap<usize, Vec<(PathBuf, &IslandDefinition)>> = BTreeMap::new();
+        for (meta_path, island_def) in found {
+            levels
+                .entry(meta_path.components().count())
+                .or_default()
+                .push((meta_path, island_def));
+        }
+        let workers = self
+            .config
+            .global
+            .scan_workers
+            .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+            .max(1);
+
+        for level in levels.values() {
+            let next = AtomicUsize::new(0);
+            thread::scope(|scope| {
+                for _ in 0..workers.min(level.len()) {
+                    scope.spawn(|| {
+                        while !job.is_cancelled() {
+                            let Some((meta_path, island_def)) =
+                                level.get(next.fetch_add(1, Ordering::Relaxed))
+                            else {
+                                break;
+                            };
+                            job.record(self.process_island(meta_path, island_def));
                         }
+                    });
+                }
+            });
+        }
+
+        if job.is_cancelled() {
+            warn!("Scan cancelled.");
+            return;
+        }
+        self.reconcile();
+        info!("✅ Initial Scan Complete.");
+    }
+
+    /// Uklanja (ili arhivira, ovisno o `on_missing`) otoke čiji meta fajl više
+    /// ne postoji na disku. Vraća broj zahvaćenih redaka.
+    pub fn reconcile(&self) -> usize {
+        let all: Vec<&IslandDefinition> = self.config.islands.iter().collect();
+        self.reconcile_types(&all)
+    }
+
+    /// `reconcile` ograničen na zadane tipove otoka.
+    fn reconcile_types(&self, island_defs: &[&IslandDefinition]) -> usize {
+        // Greške za obrisane meta fajlove više nisu relevantne.
+        for error in self.cloud.fetch_scan_errors(None).unwrap_or_default() {
+            if let Some(path) = error["path"].as_str().filter(|p| !Path::new(p).exists()) {
+                let _ = self.cloud.clear_scan_error(path);
+            }
+        }
+
+        let mut affected = 0;
+        for island_def in island_defs {
+            let rows = match self.cloud.fetch_all_dynamic(&island_def.name) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    error!("Reconcile failed for '{}': {}", island_def.name, e);
+                    continue;
+                }
+            };
+            let archive = island_def.on_missing == MissingPolicy::Archive;
+
+            for row in rows {
+                let (Some(id), Some(path)) = (row["id"].as_str(), row["path"].as_str()) else {
+                    continue;
+                };
+                let dir = Path::new(path);
+                // Redak iz vremena prije kanonskih putanja: novi redak ga je već zamijenio.
+                let superseded = canonical_path(dir) != dir;
+                let meta_path = dir.join(&island_def.meta_file);
+                let present = meta_path.exists() && self.is_classified_as(&meta_path, island_def);
+                if !superseded && (present || (archive && row["status"] == "Missing")) {
+                    continue;
+                }
+
+                let archive = archive && !superseded;
+                let result = if archive {
+                    self.cloud.mark_island_missing(&island_def.name, id)
+                } else {
+                    self.cloud.delete_island(&island_def.name, id)
+                };
+                match result {
+                    Ok(()) => {
+                        info!(
+                            "🗑️  Island gone from disk: {:?} ({})",
+                            path,
+                            if archive { "archived" } else { "deleted" }
+                        );
+                        self.cloud.events().publish(ValterEvent::IslandRemoved {
+                            island_type: island_def.name.clone(),
+                            id: id.to_string(),
+                            archived: archive,
+                        });
+                        affected += 1;
                     }
+                    Err(e) => error!("Failed to remove stale island {:?}: {}", path, e),
                 }
             }
         }
-        info!("✅ Initial Scan Complete.");
+        affected
     }
 
     pub async fn handle_event(&self, event: Event) {
-        for path in event.paths {
-            // 1. Pokušaj naći Island Definiciju koja odgovara ovom fajlu
-            if let Some(island_def) = self.find_matching_island_def(&path) {
-                info!(
-                    "⚡ Metadata Change Detected: {:?} (Type: {})",
-                    path, island_def.name
-                );
-                let _ = self.process_metadata(&path, island_def);
-            }
-            // 2. Ako nije meta fajl, možda je sub-file (retrigger deep scan)
-            // ISPRAVAK: Korištenje `is_some_and` za čišći kod
-            else if path
-                .extension()
-                .is_some_and(|ext| ext == "yaml" || ext == "md" || ext == "txt")
-            {
-                // Penjemo se gore dok ne nađemo meta fajl koji definira Island
-                let mut current = path.parent();
-                while let Some(dir) = current {
-                    // Provjeri ima li ovaj folder ikakav meta fajl definiran u configu
-                    if let Some(parent_def) = self.find_active_meta_in_dir(dir) {
+        self.handle_events(vec![event]).await;
+    }
+
+    /// Obrađuje skupinu događaja (npr. jedan prozor debouncera). Svaki zahvaćeni
+    /// otok obrađuje se samo jednom, roditelji prije djece.
+    pub async fn handle_events(&self, events: Vec<Event>) {
+        let mut islands = BTreeSet::new();
+        let mut vanished = Vec::new();
+
+        for event in events {
+            // Folder premješten ili kopiran unutar praćene lokacije: skeniramo ga cijelog.
+            let may_bring_folders = matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+            );
+            for path in event.paths {
+                if !path.exists() {
+                    vanished.push(canonical_path(&path));
+                } else if may_bring_folders && path.is_dir() {
+                    self.collect_islands_under(&path, &mut islands);
+                    self.collect_ancestors(&path, &mut islands);
+                    continue;
+                }
+
+                // 1. Pokušaj naći Island Definiciju koja odgovara ovom fajlu
+                if let Some(island_def) = self.classify(&path) {
+                    let Some(dir) = path.parent().filter(|_| path.exists()) else {
+                        continue;
+                    };
+                    info!(
+                        "⚡ Metadata Change Detected: {:?} (Type: {})",
+                        path, island_def.name
+                    );
+                    islands.insert(canonical_path(dir));
+                    // Djeca dobivaju (novi) `parent_id`.
+                    for child in self.child_island_dirs(dir) {
+                        if self.nearest_parent_dir(&child).as_deref() == Some(dir) {
+                            islands.insert(canonical_path(&child));
+                        }
+                    }
+                    self.collect_ancestors(dir, &mut islands);
+                }
+                // 2. Ako nije meta fajl, možda je sub-file (retrigger deep scan)
+                // ISPRAVAK: Korištenje `is_some_and` za čišći kod
+                else if path.extension().is_some_and(|ext| {
+                    matches!(
+                        ext.to_str(),
+                        Some("yaml" | "yml" | "md" | "txt" | "json" | "toml")
+                    )
+                }) {
+                    // Penjemo se gore dok ne nađemo meta fajl koji definira Island
+                    if let Some(dir) = self.nearest_parent_dir(&path) {
                         info!("🔄 Deep scan triggered by sub-file change in {:?}", dir);
-                        let meta_path = dir.join(&parent_def.meta_file);
-                        let _ = self.process_metadata(&meta_path, parent_def);
-                        break;
+                        self.collect_ancestors(&dir, &mut islands);
+                        islands.insert(canonical_path(&dir));
                     }
-                    current = dir.parent();
                 }
             }
         }
-    }
 
-    /// Pomoćna funkcija: Nalazi definiciju na temelju imena fajla i putanje
-    fn find_matching_island_def<'a>(&'a self, path: &Path) -> Option<&'a IslandDefinition> {
-        let file_name = path.file_name()?.to_str()?;
+        // Roditelji prije djece, da pod-projekt odmah nađe `parent_id`.
+        let mut ordered: Vec<PathBuf> = islands.into_iter().collect();
+        ordered.sort_by_key(|dir| dir.components().count());
+        for dir in ordered {
+            if let Some(island_def) = self.find_active_meta_in_dir(&dir) {
+                self.process_island(&dir.join(&island_def.meta_file), island_def);
+            }
+        }
 
-        for island in &self.config.islands {
-            if island.meta_file == file_name {
-                // Provjera putanje (Jako bitno da ne miješamo tipove ako imaju isto ime meta fajla)
-                // Jednostavna provjera: Da li putanja fajla počinje s root pathom islanda?
-                // Moramo maknuti glob charove.
-                let root_clean = island.root_path.replace('*', "").replace("./", "");
-                // Oprez: Canonicalization bi bilo idealno, ali za sada string match:
-                let path_str = path.to_string_lossy();
+        // Usklađivanje tek nakon obrade novih putanja, da premješteni otok
+        // zadrži svoj redak (isti `valter_id`) umjesto da bude obrisan.
+        let gone = self.vanished_island_types(&vanished);
+        if !gone.is_empty() {
+            self.reconcile_types(&gone);
+        }
+    }
 
-                // Hack za dev environment gdje su pathovi relativni vs apsolutni
-                if path_str.contains(&root_clean) {
-                    return Some(island);
+    /// Tipovi otoka kojima je nestao meta fajl ili folder (i folder iznad njih).
+    /// Ostali nestali fajlovi (swap fajlovi, obrisani dokumenti, `meta.tmp`) ne
+    /// pokreću usklađivanje.
+    fn vanished_island_types(&self, vanished: &[PathBuf]) -> Vec<&IslandDefinition> {
+        if vanished.is_empty() {
+            return Vec::new();
+        }
+        self.config
+            .islands
+            .iter()
+            .filter(|island_def| {
+                let meta_file = Path::new(&island_def.meta_file);
+                if vanished.iter().any(|p| p.ends_with(meta_file)) {
+                    return true;
                 }
+                let rows = self.cloud.fetch_all_dynamic(&island_def.name).unwrap_or_default();
+                rows.iter().filter_map(|row| row["path"].as_str()).any(|island_path| {
+                    vanished.iter().any(|p| Path::new(island_path).starts_with(p))
+                })
+            })
+            .collect()
+    }
+
+    /// Svi folderi otoka unutar `dir`, uključujući i njega samog.
+    fn collect_islands_under(&self, dir: &Path, islands: &mut BTreeSet<PathBuf>) {
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_dir() && self.find_active_meta_in_dir(entry.path()).is_some() {
+                islands.insert(canonical_path(entry.path()));
             }
         }
-        None
+    }
+
+    /// Roditelji preračunavaju agregacije jer sadržaj djeteta utječe na njih.
+    fn collect_ancestors(&self, dir: &Path, islands: &mut BTreeSet<PathBuf>) {
+        let mut current = self.nearest_parent_dir(dir);
+        while let Some(parent) = current {
+            current = self.nearest_parent_dir(&parent);
+            islands.insert(canonical_path(&parent));
+        }
+    }
+
+    /// Najbliži folder iznad `dir` koji je i sam otok.
+    fn nearest_parent_dir(&self, dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .skip(1)
+            .find(|a| self.find_active_meta_in_dir(a).is_some())
+            .map(Path::to_path_buf)
+    }
+
+    /// Folderi ugniježđenih otoka (na bilo kojoj dubini) ispod `root`.
+    fn child_island_dirs(&self, root: &Path) -> Vec<PathBuf> {
+        WalkDir::new(root)
+            .min_depth(1)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_dir() && self.find_active_meta_in_dir(e.path()).is_some())
+            .map(|e| e.into_path())
+            .collect()
+    }
+
+    /// ID najbližeg nadređenog otoka, ako je već upisan u bazu.
+    fn parent_island_id(&self, root: &Path) -> Option<String> {
+        let parent = self.nearest_parent_dir(root)?;
+        let def = self.find_active_meta_in_dir(&parent)?;
+        let path = canonical_path(&parent);
+        self.cloud
+            .find_island_id_by_path(&def.name, path.to_string_lossy().as_ref())
+            .ok()
+            .flatten()
     }
 
     /// Pomoćna funkcija: Provjerava postoji li validan meta fajl u direktoriju
     fn find_active_meta_in_dir<'a>(&'a self, dir: &Path) -> Option<&'a IslandDefinition> {
-        for island in &self.config.islands {
+        self.config.islands.iter().find_map(|island| {
             let candidate = dir.join(&island.meta_file);
             if candidate.exists() {
-                return Some(island);
+                self.classify(&candidate)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn is_classified_as(&self, meta_path: &Path, island_def: &IslandDefinition) -> bool {
+        self.classify(meta_path).is_some_and(|d| d.name == island_def.name)
+    }
+
+    /// Određuje točno jedan tip otoka za meta fajl. Kandidati su definicije čiji
+    /// `meta_file`, `root_path` i `match_path` odgovaraju; kandidat s pravilima
+    /// (`match`/`match_path`) koja prolaze ima prednost pred onim bez pravila.
+    /// Nejednoznačan slučaj se preskače uz upozorenje. Meta fajl koji se ne da
+    /// pročitati ostaje tipu koji već ima redak za taj folder (inače prvom
+    /// kandidatu), da obrada zabilježi grešku umjesto da otok nestane.
+    fn classify<'a>(&'a self, meta_path: &Path) -> Option<&'a IslandDefinition> {
+        let file_name = meta_path.file_name()?.to_str()?;
+        let dir = meta_path.parent()?;
+        let candidates: Vec<&IslandDefinition> = self
+            .config
+            .islands
+            .iter()
+            .filter(|i| {
+                i.meta_file == file_name && self.is_island_dir(i, dir) && matches_path_rules(i, dir)
+            })
+            .collect();
+
+        let has_rules =
+            |i: &IslandDefinition| !i.match_fields.is_empty() || !i.match_path.is_empty();
+        if candidates.len() <= 1 && candidates.iter().all(|i| i.match_fields.is_empty()) {
+            return candidates.first().copied();
+        }
+
+        let yaml: Option<Value> =
+            fs::read_to_string(meta_path).ok().and_then(|c| serde_yaml::from_str(&c).ok());
+        if yaml.is_none() && !candidates.is_empty() {
+            let path = canonical_path(dir);
+            let path = path.to_string_lossy();
+            let known = candidates.iter().copied().find(|i| {
+                self.cloud.find_island_id_by_path(&i.name, &path).ok().flatten().is_some()
+            });
+            return known.or(candidates.first().copied());
+        }
+        let specific: Vec<&IslandDefinition> = candidates
+            .iter()
+            .copied()
+            .filter(|i| has_rules(i) && yaml.as_ref().is_some_and(|y| matches_fields(i, y)))
+            .collect();
+        let chosen = if specific.is_empty() {
+            candidates.into_iter().filter(|i| !has_rules(i)).collect()
+        } else {
+            specific
+        };
+
+        match chosen.as_slice() {
+            [] => None,
+            [one] => Some(one),
+            many => {
+                let names: Vec<&str> = many.iter().map(|i| i.name.as_str()).collect();
+                warn!(
+                    "Ambiguous island type for {:?}: {}. Add `match` rules to disambiguate.",
+                    meta_path,
+                    names.join(", ")
+                );
+                None
+            }
+        }
+    }
+
+    /// Obrađuje meta fajl i ažurira `scan_errors`: neuspjeh se bilježi, a uspjeh
+    /// briše prethodnu grešku za isti fajl. Vraća je li obrada uspjela.
+    fn process_island(&self, meta_path: &Path, island_def: &IslandDefinition) -> bool {
+        let key = canonical_path(meta_path).to_string_lossy().to_string();
+        match self.process_metadata(meta_path, island_def) {
+            Ok(()) => {
+                if self.cloud.clear_scan_error(&key).unwrap_or(false) {
+                    info!("✅ {:?} is valid again", meta_path);
+                }
+                true
+            }
+            Err(e) => {
+                error!("❌ Failed to process {:?}: {}", meta_path, e);
+                let (kind, location) = classify_error(&e);
+                let record = ScanError {
+                    path: key,
+                    island_type: island_def.name.clone(),
+                    kind,
+                    message: e.to_string(),
+                    line: location.map(|(line, _)| line),
+                    column: location.map(|(_, column)| column),
+                };
+                if let Err(e) = self.cloud.record_scan_error(&record) {
+                    error!("Failed to record scan error for {:?}: {}", meta_path, e);
+                }
+                false
             }
         }
-        None
     }
 
     fn process_metadata(&self, path: &Path, island_def: &IslandDefinition) -> anyhow::Result<()> {
@@ -128,61 +466,328 @@ impl EventProcessor {
 
         let project_name = yaml.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown Project");
 
-        let project_root = path.parent().unwrap();
+        let project_root = canonical_path(path.parent().unwrap());
+        let project_root = project_root.as_path();
         let mut relation_map: HashMap<String, Option<String>> = HashMap::new();
+        let mut link_map: HashMap<String, Vec<(String, Option<String>)>> = HashMap::new();
 
         // RELATIONS LOGIC
         for rel in &island_def.relations {
-            if let Some(val_raw) = yaml.get(&rel.field) {
-                if let Some(val_str) = val_raw.as_str() {
-                    // Dinamičko traženje ID polja u target cloudu
-                    let target_cloud_def =
-                        self.config.clouds.iter().find(|c| c.name == rel.target_cloud);
-                    let key_field = target_cloud_def
-                        .and_then(|def| def.fields.first())
-                        .map(|f| f.key.as_str())
-                        .unwrap_or("id");
-
-                    let context_info = json!({
-                        "source_island_type": island_def.name,
-                        "source_island_name": project_name,
-                        "field": rel.field
-                    })
-                    .to_string();
-
-                    match self.cloud.check_or_create_pending(
-                        &rel.target_cloud,
-                        key_field,
-                        val_str,
-                        &context_info,
-                    ) {
-                        Ok(status) => match status {
-                            EntityStatus::Found(uuid) => {
-                                relation_map.insert(rel.field.clone(), Some(uuid));
-                            }
-                            EntityStatus::Pending(_) | EntityStatus::Ambiguous(_, _) => {
-                                warn!("Relation '{}' ({}) is PENDING review.", rel.field, val_str);
-                                relation_map.insert(rel.field.clone(), None);
-                            }
-                        },
-                        Err(e) => error!("Check error for {}: {}", rel.target_cloud, e),
-                    }
+            let val_raw = yaml.get(&rel.field);
+            match rel.cardinality {
+                Cardinality::One => {
+                    // Redak se ažurira na mjestu, pa i uklonjena relacija mora postati NULL.
+                    let resolved = val_raw
+                        .and_then(|v| v.as_str())
+                        .and_then(|v| self.resolve_reference(island_def, project_name, rel, v))
+                        .flatten();
+                    relation_map.insert(rel.field.clone(), resolved);
+                }
+                Cardinality::Many => {
+                    // Prihvaćamo i listu i pojedinačnu vrijednost (lista s jednim elementom).
+                    let values: Vec<String> = match val_raw {
+                        Some(Value::Sequence(items)) => {
+                            items.iter().filter_map(scalar_to_string).collect()
+                        }
+                        Some(v) => scalar_to_string(v).into_iter().collect(),
+                        None => Vec::new(),
+                    };
+                    let links = values
+                        .into_iter()
+                        .filter_map(|v| {
+                            let resolved =
+                                self.resolve_reference(island_def, project_name, rel, &v)?;
+                            Some((v, resolved))
+                        })
+                        .collect();
+                    link_map.insert(rel.field.clone(), links);
                 }
             }
         }
 
+        let (field_values, invalid_fields) = meta_fields(&yaml, island_def);
+
         // AGGREGATION LOGIC
-        let aggregation_results = Aggregator::calculate(project_root, &island_def.aggregations)?;
+        let root = project_root.to_string_lossy();
+        let children = self.child_island_dirs(project_root);
+        let mut cache = self.cloud.load_file_cache(&root)?;
+        let aggregation_results = Aggregator::calculate(
+            project_root,
+            &island_def.aggregations,
+            &island_def.derived,
+            &children,
+            &mut cache,
+        )?;
+        if let Err(e) = self.cloud.save_file_cache(&root, &cache) {
+            warn!("Aggregation cache not saved for {:?}: {}", project_root, e);
+        }
 
         // UPSERT
+        // Provjera `valter_id` i upis idu zajedno, da paralelni radnici ne
+        // zadrže isti ID za dvije kopije foldera.
+        let _claim = self.claim_lock.lock().unwrap();
+        let id = self.island_id(path, &yaml, island_def);
         self.cloud.upsert_island(
             &island_def.name,
+            &id,
             project_name,
-            project_root.to_string_lossy().as_ref(),
+            root.as_ref(),
+            self.parent_island_id(project_root).as_deref(),
+            &field_values,
             &relation_map,
             &aggregation_results,
         )?;
+        for (field, links) in &link_map {
+            self.cloud.set_island_links(&island_def.name, &id, field, links)?;
+        }
+        self.cloud.set_island_tags(&island_def.name, &id, &meta_tags(&yaml))?;
+        self.cloud.events().publish(ValterEvent::IslandUpserted {
+            island_type: island_def.name.clone(),
+            id,
+            name: project_name.to_string(),
+            path: root.to_string(),
+        });
 
+        // Neispravna polja ne blokiraju otok; upisana su kao NULL, a greška
+        // ostaje vidljiva u `scan_errors`.
+        if !invalid_fields.is_empty() {
+            return Err(ValidationError(invalid_fields.join("; ")).into());
+        }
         Ok(())
     }
+
+    /// Stabilni ID otoka. Kod `identity: file` čita `valter_id` iz meta fajla ili ga
+    /// upisuje pri prvom susretu; ako upis ne uspije, koristi se ID izveden iz putanje.
+    fn island_id(&self, meta_path: &Path, yaml: &Value, island_def: &IslandDefinition) -> String {
+        let root = meta_path.parent().unwrap_or(meta_path);
+        if island_def.identity == IslandIdentity::Path {
+            return path_id(root);
+        }
+
+        if let Some(id) = yaml.get(VALTER_ID_KEY).and_then(|v| v.as_str()) {
+            if !self.is_claimed_elsewhere(id, root, island_def) {
+                return id.to_string();
+            }
+            // Kopirani folder nosi tuđi `valter_id`; dobiva vlastiti.
+            warn!(
+                "Duplicate {} '{}' in {:?}. Assigning a new one.",
+                VALTER_ID_KEY, id, root
+            );
+        }
+
+        let id = Uuid::new_v4().to_string();
+        match FsWriter::update_yaml_field(meta_path, VALTER_ID_KEY, &id) {
+            Ok(()) => id,
+            Err(e) => {
+                warn!(
+                    "Could not write {} to {:?}: {}",
+                    VALTER_ID_KEY, meta_path, e
+                );
+                path_id(root)
+            }
+        }
+    }
+
+    /// Je li `id` već zauzet otokom na drugoj putanji čiji meta fajl još postoji
+    /// i nosi isti `valter_id`? Ako je stari folder nestao, radi se o premještanju.
+    fn is_claimed_elsewhere(&self, id: &str, root: &Path, island_def: &IslandDefinition) -> bool {
+        let Ok(Some(row)) = self.cloud.fetch_by_id(&island_def.name, id) else {
+            return false;
+        };
+        let Some(other) = row.get("path").and_then(|p| p.as_str()) else {
+            return false;
+        };
+        if Path::new(other) == root {
+            return false;
+        }
+        fs::read_to_string(Path::new(other).join(&island_def.meta_file))
+            .ok()
+            .and_then(|c| serde_yaml::from_str::<Value>(&c).ok())
+            .is_some_and(|y| y.get(VALTER_ID_KEY).and_then(|v| v.as_str()) == Some(id))
+    }
+
+    /// Traži Cloud entitet za vrijednost relacije (ili otvara pending akciju).
+    /// `Some(None)` znači da relacija čeka odobrenje, `None` da je provjera pala.
+    fn resolve_reference(
+        &self,
+        island_def: &IslandDefinition,
+        project_name: &str,
+        rel: &RelationRule,
+        value: &str,
+    ) -> Option<Option<String>> {
+        // Dinamičko traženje ID polja u target cloudu
+        let target_cloud_def = self.config.clouds.iter().find(|c| c.name == rel.target_cloud);
+        let key_field = target_cloud_def
+            .and_then(|def| def.fields.first())
+            .map(|f| f.key.as_str())
+            .unwrap_or("id");
+
+        let context_info = json!({
+            "source_island_type": island_def.name,
+            "source_island_name": project_name,
+            "field": rel.field
+        })
+        .to_string();
+
+        match self
+            .cloud
+            .check_or_create_pending(&rel.target_cloud, key_field, value, &context_info)
+        {
+            Ok(EntityStatus::Found(uuid)) => Some(Some(uuid)),
+            Ok(EntityStatus::Pending(_) | EntityStatus::Ambiguous(_, _)) => {
+                warn!("Relation '{}' ({}) is PENDING review.", rel.field, value);
+                Some(None)
+            }
+            Err(e) => {
+                error!("Check error for {}: {}", rel.target_cloud, e);
+                None
+            }
+        }
+    }
+}
+
+fn matches_path_rules(island: &IslandDefinition, dir: &Path) -> bool {
+    if island.match_path.is_empty() {
+        return true;
+    }
+    let dir = canonical_path(dir);
+    island.match_path.iter().any(|p| {
+        glob::Pattern::new(p).is_ok_and(|pattern| {
+            pattern.matches_path_with(
+                &dir,
+                glob::MatchOptions {
+                    case_sensitive: true,
+                    require_literal_separator: true,
+                    require_literal_leading_dot: false,
+                },
+            )
+        })
+    })
+}
+
+/// Sva `match` pravila moraju proći. Vrijednosti se uspoređuju kao tekst, lista
+/// u pravilu znači "bilo koja od", a lista u meta fajlu "sadrži".
+fn matches_fields(island: &IslandDefinition, yaml: &Value) -> bool {
+    island.match_fields.iter().all(|(key, expected)| {
+        let Ok(path) = FieldPath::parse(key) else {
+            return false;
+        };
+        path.resolve(yaml).into_iter().any(|actual| value_matches(actual, expected))
+    })
+}
+
+fn value_matches(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (_, Value::Sequence(options)) => options.iter().any(|o| value_matches(actual, o)),
+        (Value::Sequence(items), _) => items.iter().any(|i| value_matches(i, expected)),
+        _ => scalar_to_string(actual).is_some_and(|a| scalar_to_string(expected) == Some(a)),
+    }
+}
+
+/// Vrijednosti stupaca iz meta fajla: deklarirana `fields` (validirana kao Cloud
+/// polja), `status` i, uz `capture_extra`, svi ostali ključevi kao JSON. Polje koje
+/// ne prođe validaciju upisuje se kao NULL, a poruka se vraća zasebno.
+///
+/// `status` iz meta fajla uvijek ima prednost: `Missing` postavlja samo
+/// `reconcile` dok folder ne postoji, a otok koji se vratio dobiva status iz
+/// meta fajla. Nedeklarirani `status` prepisuje se kao tekst.
+fn meta_fields(
+    yaml: &Value,
+    island_def: &IslandDefinition,
+) -> (Vec<(String, SqlValue)>, Vec<String>) {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for field in &island_def.fields {
+        let mut data = serde_json::Map::new();
+        if let Some(v) = yaml.get(&field.key).and_then(|v| serde_json::to_value(v).ok()) {
+            data.insert(field.key.clone(), v);
+        }
+        match fields::validate_record(std::slice::from_ref(field), &data, false) {
+            Ok(v) => values.extend(v),
+            Err(e) => {
+                errors.push(match e.downcast_ref::<ValidationError>() {
+                    Some(ValidationError(message)) => message.clone(),
+                    None => e.to_string(),
+                });
+                values.push((field.key.clone(), SqlValue::Null));
+            }
+        }
+    }
+    if !island_def.fields.iter().any(|f| f.key == STATUS_COLUMN) {
+        let status = yaml.get(STATUS_COLUMN).and_then(scalar_to_string);
+        values.push((
+            STATUS_COLUMN.to_string(),
+            status.map_or(SqlValue::Null, SqlValue::Text),
+        ));
+    }
+
+    if island_def.capture_extra {
+        let known: HashSet<&str> = ["name", STATUS_COLUMN, TAGS_KEY, VALTER_ID_KEY]
+            .into_iter()
+            .chain(island_def.fields.iter().map(|f| f.key.as_str()))
+            .chain(island_def.relations.iter().map(|r| r.field.as_str()))
+            .collect();
+        let extra: serde_json::Map<String, serde_json::Value> = yaml
+            .as_mapping()
+            .into_iter()
+            .flatten()
+            .filter_map(|(k, v)| {
+                let key = k.as_str().filter(|k| !known.contains(k))?;
+                Some((key.to_string(), serde_json::to_value(v).ok()?))
+            })
+            .collect();
+        let extra = serde_json::Value::Object(extra).to_string();
+        values.push((EXTRA_COLUMN.to_string(), SqlValue::Text(extra)));
+    }
+    (values, errors)
+}
+
+/// Tagovi iz meta fajla: lista ili string odvojen zarezima. Prazni se
+/// preskaču, a duplikati (bez obzira na velika/mala slova) spajaju.
+fn meta_tags(yaml: &Value) -> Vec<String> {
+    let raw: Vec<String> = match yaml.get(TAGS_KEY) {
+        Some(Value::Sequence(items)) => items.iter().filter_map(scalar_to_string).collect(),
+        Some(v) => scalar_to_string(v)
+            .map(|s| s.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let mut tags: Vec<String> = Vec::new();
+    for tag in raw.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Vrsta greške i (za YAML) linija/stupac.
+fn classify_error(error: &anyhow::Error) -> (ScanErrorKind, Option<(usize, usize)>) {
+    if let Some(e) = error.downcast_ref::<serde_yaml::Error>() {
+        let location = e.location().map(|l| (l.line(), l.column()));
+        (ScanErrorKind::Yaml, location)
+    } else if error.downcast_ref::<std::io::Error>().is_some() {
+        (ScanErrorKind::Io, None)
+    } else if error.downcast_ref::<rusqlite::Error>().is_some() {
+        (ScanErrorKind::Database, None)
+    } else if error.downcast_ref::<ValidationError>().is_some() {
+        (ScanErrorKind::Validation, None)
+    } else {
+        (ScanErrorKind::Processing, None)
+    }
+}
+
+/// Deterministički ID iz (kanonske) putanje foldera.
+fn path_id(root: &Path) -> String {
+    let canonical = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, canonical.to_string_lossy().as_bytes()).to_string()
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
 }
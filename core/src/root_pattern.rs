@@ -0,0 +1,278 @@
+// core/src/root_pattern.rs
+
+//! Glob semantika za `root_path` otoka: `*`, `?`, `[..]`, `**` i `{a,b}`.
+//!
+//! Uzorak opisuje folder otoka (npr. `/data/*/projects/*`), a meta fajl se traži
+//! unutar tog foldera. Sve putanje se uspoređuju u kanonskom obliku, pa relativni
+//! i apsolutni zapis iste lokacije daju isti rezultat.
+
+use crate::config::IslandDefinition;
+use anyhow::{bail, Result};
+use glob::{MatchOptions, Pattern};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Jedna alternativa nakon razvijanja `{a,b}`: statički početak i glob ostatak.
+#[derive(Debug, Clone)]
+struct Alternative {
+    base: PathBuf,
+    rest: Option<Pattern>,
+    /// Točna dubina ispod `base` kad uzorak nema `**`.
+    depth: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RootPattern {
+    alternatives: Vec<Alternative>,
+    max_depth: Option<usize>,
+}
+
+impl RootPattern {
+    /// `max_depth` ograničava koliko duboko ispod statičkog početka `**` smije ići.
+    pub fn parse(raw: &str, max_depth: Option<usize>) -> Result<Self> {
+        let mut alternatives = Vec::new();
+        for expanded in expand_braces(raw)? {
+            let parts: Vec<&str> = expanded.split('/').collect();
+            let split =
+                parts.iter().position(|p| p.contains(['*', '?', '['])).unwrap_or(parts.len());
+
+            let mut base = parts[..split].join("/");
+            if base.is_empty() && expanded.starts_with('/') {
+                base = "/".to_string();
+            }
+            let rest: Vec<&str> =
+                parts[split..].iter().copied().filter(|p| !p.is_empty()).collect();
+
+            let pattern = if rest.is_empty() {
+                None
+            } else {
+                match Pattern::new(&rest.join("/")) {
+                    Ok(p) => Some(p),
+                    Err(e) => bail!("Invalid root_path '{}': {}", raw, e),
+                }
+            };
+            alternatives.push(Alternative {
+                base: canonical_path(Path::new(&base)),
+                depth: (!rest.contains(&"**")).then_some(rest.len()),
+                rest: pattern,
+            });
+        }
+        Ok(Self {
+            alternatives,
+            max_depth,
+        })
+    }
+
+    /// Je li `dir` folder otoka po ovom uzorku?
+    pub fn matches_dir(&self, dir: &Path) -> bool {
+        let dir = canonical_path(dir);
+        self.alternatives.iter().any(|alt| {
+            let Ok(rel) = dir.strip_prefix(&alt.base) else {
+                return false;
+            };
+            let depth = rel.components().count();
+            match &alt.rest {
+                None => depth == 0,
+                Some(pattern) => {
+                    depth > 0
+                        && self.max_depth.is_none_or(|max| depth <= max)
+                        && pattern.matches_path_with(rel, MATCH_OPTIONS)
+                }
+            }
+        })
+    }
+
+    /// Svi postojeći folderi otoka, sortirani.
+    pub fn discover(&self) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        for alt in &self.alternatives {
+            if !alt.base.is_dir() {
+                continue;
+            }
+            if alt.rest.is_none() {
+                found.push(alt.base.clone());
+                continue;
+            }
+            let limit = alt.depth.or(self.max_depth).unwrap_or(usize::MAX);
+            for entry in WalkDir::new(&alt.base)
+                .min_depth(1)
+                .max_depth(limit)
+                .into_iter()
+                .filter_map(|e| e.ok())
+            {
+                if entry.file_type().is_dir() && self.matches_dir(entry.path()) {
+                    found.push(canonical_path(entry.path()));
+                }
+            }
+        }
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// Statički počeci uzorka, tj. što watcher treba pratiti.
+    pub fn watch_roots(&self) -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = self.alternatives.iter().map(|a| a.base.clone()).collect();
+        roots.dedup();
+        roots
+    }
+
+    /// Folder u kojem se stvaraju novi otoci; postoji samo za jednostavan `<dir>/*`.
+    pub fn creation_dir(&self) -> Option<PathBuf> {
+        match self.alternatives.as_slice() {
+            [alt] if alt.rest.as_ref().is_some_and(|p| p.as_str() == "*") => Some(alt.base.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IslandDefinition {
+    pub fn root_pattern(&self) -> Result<RootPattern> {
+        RootPattern::parse(&self.root_path, self.max_depth)
+    }
+}
+
+/// Kanonska putanja i za putanje koje (više) ne postoje: kanonizira se najbliži
+/// postojeći predak, a ostatak se dodaje leksički.
+pub fn canonical_path(path: &Path) -> PathBuf {
+    if let Ok(p) = fs::canonicalize(path) {
+        return p;
+    }
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+
+    let mut clean = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                clean.pop();
+            }
+            other => clean.push(other),
+        }
+    }
+
+    let mut existing = clean.as_path();
+    let mut tail = Vec::new();
+    while !existing.exists() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            return clean;
+        };
+        tail.push(name.to_os_string());
+        existing = parent;
+    }
+    let mut result = fs::canonicalize(existing).unwrap_or_else(|_| existing.to_path_buf());
+    result.extend(tail.iter().rev());
+    result
+}
+
+/// `a/{b,c}/d` -> [`a/b/d`, `a/c/d`]; podržava ugniježđene zagrade.
+fn expand_braces(input: &str) -> Result<Vec<String>> {
+    let Some(open) = input.find('{') else {
+        if input.contains('}') {
+            bail!("Unmatched '}}' in root_path '{}'", input);
+        }
+        return Ok(vec![input.to_string()]);
+    };
+
+    let mut level = 0;
+    let mut close = None;
+    let mut splits = Vec::new();
+    for (i, c) in input[open..].char_indices() {
+        match c {
+            '{' => level += 1,
+            '}' => {
+                level -= 1;
+                if level == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            ',' if level == 1 => splits.push(open + i),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        bail!("Unclosed '{{' in root_path '{}'", input);
+    };
+
+    let (prefix, suffix) = (&input[..open], &input[close + 1..]);
+    let mut bounds = vec![open];
+    bounds.extend(splits);
+    bounds.push(close);
+
+    let mut results = Vec::new();
+    for pair in bounds.windows(2) {
+        let option = &input[pair[0] + 1..pair[1]];
+        results.extend(expand_braces(&format!("{}{}{}", prefix, option, suffix))?);
+    }
+    Ok(results)
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_expand_braces() {
+        assert_eq!(
+            expand_braces("/d/{a,b{1,2}}/*").unwrap(),
+            vec!["/d/a/*", "/d/b1/*", "/d/b2/*"]
+        );
+        assert!(expand_braces("/d/{a,b/*").is_err());
+    }
+
+    #[test]
+    fn test_matching_and_discovery() {
+        let dir = tempdir().unwrap();
+        let root = canonical_path(dir.path());
+        for sub in [
+            "acme/projects/phoenix",
+            "acme/archive/old",
+            "globex/projects/nova",
+            "deep/x/clients/c1",
+            "deep/x/y/z/clients/c2",
+        ] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        let r = root.to_string_lossy();
+
+        let projects = RootPattern::parse(&format!("{}/*/projects/*", r), None).unwrap();
+        assert_eq!(
+            projects.discover(),
+            vec![
+                root.join("acme/projects/phoenix"),
+                root.join("globex/projects/nova")
+            ]
+        );
+        assert!(!projects.matches_dir(&root.join("acme/archive/old")));
+        // Relativni zapis iste lokacije se kanonizira.
+        assert!(projects.matches_dir(&root.join("acme/./projects/../projects/phoenix")));
+
+        let clients = RootPattern::parse(&format!("{}/**/clients/*", r), None).unwrap();
+        assert_eq!(clients.discover().len(), 2);
+        let shallow = RootPattern::parse(&format!("{}/**/clients/*", r), Some(4)).unwrap();
+        assert_eq!(shallow.discover(), vec![root.join("deep/x/clients/c1")]);
+
+        let braces =
+            RootPattern::parse(&format!("{}/{{acme,globex}}/projects/*", r), None).unwrap();
+        assert_eq!(braces.discover().len(), 2);
+        assert_eq!(braces.watch_roots().len(), 2);
+        assert_eq!(braces.creation_dir(), None);
+
+        let simple = RootPattern::parse(&format!("{}/acme/projects/*", r), None).unwrap();
+        assert_eq!(simple.creation_dir(), Some(root.join("acme/projects")));
+    }
+}
@@ -0,0 +1,268 @@
+// core/src/scan.rs
+
+//! Skeniranje otoka kao pozadinski posao. `rescanIslands` vraća ID posla odmah,
+//! a napredak (pronađeno/obrađeno/neuspjelo, procjena preostalog vremena) se
+//! čita preko `scanProgress`. Posao se može otkazati; otkazivanje se provjerava
+//! između otoka.
+
+use crate::processor::EventProcessor;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::info;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ScanState {
+    Running,
+    Completed,
+    Cancelled,
+    /// Radnik je pao (panic) prije kraja skeniranja.
+    Failed,
+}
+
+/// Koliko završenih poslova registar čuva za `scanProgress`.
+const KEEP_FINISHED_JOBS: usize = 16;
+
+/// Snimka stanja posla za API.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    pub job_id: String,
+    pub state: ScanState,
+    pub found: usize,
+    pub processed: usize,
+    pub failed: usize,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// Procjena preostalih sekundi, dok posao traje i ima barem jedan obrađen otok.
+    pub eta_seconds: Option<f64>,
+}
+
+pub struct ScanJob {
+    id: String,
+    found: AtomicUsize,
+    processed: AtomicUsize,
+    failed: AtomicUsize,
+    cancelled: AtomicBool,
+    crashed: AtomicBool,
+    started: Instant,
+    started_at: String,
+    finished_at: Mutex<Option<String>>,
+}
+
+impl ScanJob {
+    pub fn new() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            found: AtomicUsize::new(0),
+            processed: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            cancelled: AtomicBool::new(false),
+            crashed: AtomicBool::new(false),
+            started: Instant::now(),
+            started_at: chrono::Local::now().to_rfc3339(),
+            finished_at: Mutex::new(None),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished_at.lock().unwrap().is_some()
+    }
+
+    pub(crate) fn add_found(&self, count: usize) {
+        self.found.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Bilježi jedan obrađen otok; neuspjeli se broje i u `processed`.
+    pub(crate) fn record(&self, ok: bool) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn finish(&self) {
+        *self.finished_at.lock().unwrap() = Some(chrono::Local::now().to_rfc3339());
+    }
+
+    pub fn progress(&self) -> ScanProgress {
+        let found = self.found.load(Ordering::Relaxed);
+        let processed = self.processed.load(Ordering::Relaxed);
+        let finished_at = self.finished_at.lock().unwrap().clone();
+        let state = match (&finished_at, self.is_cancelled()) {
+            (None, _) => ScanState::Running,
+            (Some(_), _) if self.crashed.load(Ordering::Relaxed) => ScanState::Failed,
+            (Some(_), true) => ScanState::Cancelled,
+            (Some(_), false) => ScanState::Completed,
+        };
+        let eta_seconds = (state == ScanState::Running && processed > 0).then(|| {
+            let per_island = self.started.elapsed().as_secs_f64() / processed as f64;
+            per_island * found.saturating_sub(processed) as f64
+        });
+
+        ScanProgress {
+            job_id: self.id.clone(),
+            state,
+            found,
+            processed,
+            failed: self.failed.load(Ordering::Relaxed),
+            started_at: self.started_at.clone(),
+            finished_at,
+            eta_seconds,
+        }
+    }
+}
+
+impl Default for ScanJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Završava posao i kad radnik padne, da ne ostane zauvijek `Running` i ne
+/// blokira sljedeća skeniranja.
+struct FinishGuard(Arc<ScanJob>);
+
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.crashed.store(true, Ordering::Relaxed);
+        }
+        self.0.finish();
+        let progress = self.0.progress();
+        info!(
+            "Scan job {} {:?}: {}/{} islands ({} failed)",
+            progress.job_id, progress.state, progress.processed, progress.found, progress.failed
+        );
+    }
+}
+
+/// Registar poslova skeniranja za jednu konfiguraciju.
+#[derive(Default)]
+pub struct ScanJobs {
+    jobs: Mutex<HashMap<String, Arc<ScanJob>>>,
+    latest: Mutex<Option<Arc<ScanJob>>>,
+}
+
+impl ScanJobs {
+    /// Pokreće skeniranje u pozadini. Ako jedno već traje, vraća taj posao.
+    pub fn start(&self, processor: Arc<EventProcessor>) -> Arc<ScanJob> {
+        let mut latest = self.latest.lock().unwrap();
+        if let Some(running) = latest.as_ref().filter(|j| !j.is_finished()) {
+            return running.clone();
+        }
+
+        let job = Arc::new(ScanJob::new());
+        let mut jobs = self.jobs.lock().unwrap();
+        prune_finished(&mut jobs);
+        jobs.insert(job.id.clone(), job.clone());
+        *latest = Some(job.clone());
+
+        let worker = FinishGuard(job.clone());
+        tokio::task::spawn_blocking(move || {
+            info!("🔍 Scan job {} started", worker.0.id());
+            processor.scan(&worker.0);
+        });
+        job
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<ScanJob>> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn latest(&self) -> Option<Arc<ScanJob>> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    /// Otkazuje posao koji još traje (npr. prije ponovnog učitavanja konfiguracije).
+    pub fn cancel_running(&self) {
+        if let Some(job) = self.latest() {
+            job.cancel();
+        }
+    }
+}
+
+/// Ostavlja samo `KEEP_FINISHED_JOBS - 1` najnovijih završenih poslova, da
+/// novi stane unutar granice.
+fn prune_finished(jobs: &mut HashMap<String, Arc<ScanJob>>) {
+    let mut finished: Vec<(Instant, String)> = jobs
+        .values()
+        .filter(|j| j.is_finished())
+        .map(|j| (j.started, j.id.clone()))
+        .collect();
+    let excess = (finished.len() + 1).saturating_sub(KEEP_FINISHED_JOBS);
+    finished.sort();
+    for (_, id) in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_and_eta() {
+        let job = ScanJob::new();
+        job.add_found(4);
+        assert_eq!(job.progress().eta_seconds, None);
+
+        job.record(true);
+        job.record(false);
+        let progress = job.progress();
+        assert_eq!(progress.state, ScanState::Running);
+        assert_eq!((progress.processed, progress.failed), (2, 1));
+        assert!(progress.eta_seconds.is_some());
+
+        job.cancel();
+        job.finish();
+        let progress = job.progress();
+        assert_eq!(progress.state, ScanState::Cancelled);
+        assert_eq!(progress.eta_seconds, None);
+        assert!(progress.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_panicking_worker_finishes_job() {
+        let job = Arc::new(ScanJob::new());
+        let guard = FinishGuard(job.clone());
+        let result = std::thread::spawn(move || {
+            let _guard = guard;
+            panic!("scan crashed");
+        })
+        .join();
+        assert!(result.is_err());
+        assert_eq!(job.progress().state, ScanState::Failed);
+    }
+
+    #[test]
+    fn test_finished_jobs_are_pruned() {
+        let mut jobs = HashMap::new();
+        for _ in 0..KEEP_FINISHED_JOBS + 5 {
+            let job = Arc::new(ScanJob::new());
+            job.finish();
+            jobs.insert(job.id.clone(), job);
+        }
+        let running = Arc::new(ScanJob::new());
+        jobs.insert(running.id.clone(), running.clone());
+
+        prune_finished(&mut jobs);
+        assert_eq!(jobs.len(), KEEP_FINISHED_JOBS);
+        assert!(jobs.contains_key(running.id()));
+    }
+}
@@ -0,0 +1,552 @@
+// core/src/typed_schema.rs
+
+//! Tipizirana GraphQL shema generirana iz `valter.config`.
+//!
+//! Za svaki CLOUD i ISLAND nastaje zaseban GraphQL tip s tipiziranim poljima,
+//! relacije se razrješavaju u objekt ciljanog Clouda, a agregacije postaju
+//! obična polja. Shema se gradi pri svakom (ponovnom) pokretanju API-ja, pa
+//! promjena konfiguracije automatski daje novu shemu. Poslužuje se na
+//! `/graphql/typed`, uz postojeći netipizirani `/graphql`.
+
+use crate::api::{data_query, FieldFilterInput, SortInput};
+use crate::cloud::{DataQuery, SqliteManager};
+use crate::config::{
+    AggregationLogic, Cardinality, Config, FieldType, EXTRA_COLUMN, STATUS_COLUMN, TAGS_KEY,
+};
+use async_graphql::dynamic::{
+    Field, FieldFuture, FieldValue, InputObject, InputValue, Object, ResolverContext, Scalar,
+    Schema, SchemaError, TypeRef,
+};
+use async_graphql::{Number, Value as GqlValue};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+
+const JSON_SCALAR: &str = "JSON";
+const FILTER_INPUT: &str = "FieldFilterInput";
+const SORT_INPUT: &str = "SortInput";
+
+/// Kako se vrijednost iz SQLite retka prikazuje u GraphQL-u.
+#[derive(Clone, Copy)]
+enum ColumnKind {
+    Id,
+    Text,
+    Float,
+    Int,
+    Bool,
+    TextList,
+    Json,
+    /// JSON spremljen kao tekst (npr. `extra`).
+    JsonText,
+}
+
+impl ColumnKind {
+    fn for_field(field_type: FieldType) -> Self {
+        match field_type {
+            FieldType::Number | FieldType::Currency => ColumnKind::Float,
+            FieldType::Integer => ColumnKind::Int,
+            FieldType::Boolean => ColumnKind::Bool,
+            FieldType::MultiSelect => ColumnKind::TextList,
+            _ => ColumnKind::Text,
+        }
+    }
+
+    fn for_aggregation(logic: &AggregationLogic) -> Self {
+        match logic {
+            // Min/Max/First/Last mogu vratiti i tekst (datume), pa su JSON.
+            AggregationLogic::Min
+            | AggregationLogic::Max
+            | AggregationLogic::First
+            | AggregationLogic::Last => ColumnKind::Json,
+            _ => ColumnKind::Float,
+        }
+    }
+
+    fn type_ref(self, non_null: bool) -> TypeRef {
+        let name = match self {
+            ColumnKind::Id => TypeRef::ID,
+            ColumnKind::Text => TypeRef::STRING,
+            ColumnKind::Float => TypeRef::FLOAT,
+            ColumnKind::Int => TypeRef::INT,
+            ColumnKind::Bool => TypeRef::BOOLEAN,
+            ColumnKind::TextList => return TypeRef::named_nn_list(TypeRef::STRING),
+            ColumnKind::Json | ColumnKind::JsonText => JSON_SCALAR,
+        };
+        if non_null {
+            TypeRef::named_nn(name)
+        } else {
+            TypeRef::named(name)
+        }
+    }
+
+    fn convert(self, value: &JsonValue) -> Option<GqlValue> {
+        if value.is_null() {
+            return None;
+        }
+        match self {
+            ColumnKind::Id | ColumnKind::Text => Some(GqlValue::String(match value {
+                JsonValue::String(s) => s.clone(),
+                other => other.to_string(),
+            })),
+            ColumnKind::Float => value.as_f64().and_then(Number::from_f64).map(GqlValue::Number),
+            ColumnKind::Int => value
+                .as_i64()
+                .or_else(|| value.as_f64().map(|f| f as i64))
+                .map(|i| GqlValue::Number(i.into())),
+            ColumnKind::Bool => value
+                .as_bool()
+                .or_else(|| value.as_i64().map(|i| i != 0))
+                .map(GqlValue::Boolean),
+            ColumnKind::TextList => {
+                let items: Vec<String> = serde_json::from_str(value.as_str()?).ok()?;
+                Some(GqlValue::List(
+                    items.into_iter().map(GqlValue::String).collect(),
+                ))
+            }
+            ColumnKind::Json => GqlValue::from_json(value.clone()).ok(),
+            ColumnKind::JsonText => {
+                GqlValue::from_json(serde_json::from_str(value.as_str()?).ok()?).ok()
+            }
+        }
+    }
+}
+
+/// Gradi shemu. Greška znači da konfiguracija daje nevaljana GraphQL imena.
+pub fn build(config: &Config, cloud: Arc<SqliteManager>) -> Result<Schema, SchemaError> {
+    let mut query = Object::new("Query");
+    let mut objects = Vec::new();
+
+    for cloud_def in &config.clouds {
+        let mut object = Object::new(&cloud_def.name).field(column("id", ColumnKind::Id, true));
+        for field in &cloud_def.fields {
+            let kind = ColumnKind::for_field(field.field_type);
+            // Odobreno kreiranje može ostaviti obavezna polja prazna, pa ni ona
+            // nisu non-null (jedan NULL bi inače poništio cijelu listu).
+            object = object.field(column(&field.key, kind, false));
+        }
+        // Obrnuti smjer: npr. `projectListByClient` na tipu `Client`.
+        for island in &config.islands {
+            for rel in island.relations.iter().filter(|r| r.target_cloud == cloud_def.name) {
+                object = object.field(referrers(&island.name, &rel.field, rel.cardinality));
+            }
+        }
+        objects.push(object);
+        query = root_fields(query, &cloud_def.name);
+    }
+
+    for island in &config.islands {
+        let mut object = Object::new(&island.name)
+            .field(column("id", ColumnKind::Id, true))
+            .field(column("name", ColumnKind::Text, false))
+            .field(column("path", ColumnKind::Text, false))
+            .field(column("updated_at", ColumnKind::Text, false))
+            .field(column("parent_id", ColumnKind::Id, false));
+        if !island.fields.iter().any(|f| f.key == STATUS_COLUMN) {
+            object = object.field(column(STATUS_COLUMN, ColumnKind::Text, false));
+        }
+
+        for rel in &island.relations {
+            object = match rel.cardinality {
+                Cardinality::One => object.field(relation(&rel.field, &rel.target_cloud)).field(
+                    column_as(&format!("{}_id", rel.field), &rel.field, ColumnKind::Id),
+                ),
+                Cardinality::Many => object
+                    .field(relation_list(&island.name, &rel.field, &rel.target_cloud))
+                    .field(link_ids(&island.name, &rel.field)),
+            };
+        }
+        for field in &island.fields {
+            let kind = ColumnKind::for_field(field.field_type);
+            object = object.field(column(&field.key, kind, false));
+        }
+        if island.capture_extra {
+            object = object.field(column(EXTRA_COLUMN, ColumnKind::JsonText, false));
+        }
+        object = object.field(tag_names(&island.name));
+        for agg in &island.aggregations {
+            object = object.field(column(
+                &agg.name,
+                ColumnKind::for_aggregation(&agg.logic),
+                false,
+            ));
+        }
+        for metric in &island.derived {
+            object = object.field(column(&metric.name, ColumnKind::Float, false));
+        }
+        objects.push(object);
+        query = root_fields(query, &island.name);
+    }
+
+    let filter_input = InputObject::new(FILTER_INPUT)
+        .field(InputValue::new("field", TypeRef::named_nn(TypeRef::STRING)))
+        .field(InputValue::new("eq", TypeRef::named(JSON_SCALAR)))
+        .field(InputValue::new("ne", TypeRef::named(JSON_SCALAR)))
+        .field(InputValue::new("contains", TypeRef::named(TypeRef::STRING)))
+        .field(InputValue::new("gt", TypeRef::named(JSON_SCALAR)))
+        .field(InputValue::new("gte", TypeRef::named(JSON_SCALAR)))
+        .field(InputValue::new("lt", TypeRef::named(JSON_SCALAR)))
+        .field(InputValue::new("lte", TypeRef::named(JSON_SCALAR)));
+    let sort_input = InputObject::new(SORT_INPUT)
+        .field(InputValue::new("field", TypeRef::named_nn(TypeRef::STRING)))
+        .field(InputValue::new("desc", TypeRef::named(TypeRef::BOOLEAN)));
+
+    let mut builder = Schema::build("Query", None, None)
+        .register(Scalar::new(JSON_SCALAR))
+        .register(filter_input)
+        .register(sort_input);
+    for object in objects {
+        builder = builder.register(object);
+    }
+    builder.register(query).data(cloud).finish()
+}
+
+/// `client(id)`, `clientList(...)` i `clientCount(...)` za svaku tablicu.
+fn root_fields(query: Object, type_name: &str) -> Object {
+    let base = lower_first(type_name);
+
+    let table = type_name.to_string();
+    let single = Field::new(base.clone(), TypeRef::named(type_name), move |ctx| {
+        let table = table.clone();
+        FieldFuture::new(async move {
+            let id = ctx.args.try_get("id")?.string()?;
+            let db = ctx.data::<Arc<SqliteManager>>()?;
+            Ok(db.fetch_by_id(&table, id)?.map(FieldValue::owned_any))
+        })
+    })
+    .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::ID)));
+
+    let table = type_name.to_string();
+    let list = Field::new(
+        format!("{}List", base),
+        TypeRef::named_nn_list_nn(type_name),
+        move |ctx| {
+            let table = table.clone();
+            FieldFuture::new(async move {
+                let query = list_query(&ctx)?;
+                let db = ctx.data::<Arc<SqliteManager>>()?;
+                let rows = db.fetch_dynamic(&table, &query)?;
+                Ok(Some(FieldValue::list(
+                    rows.into_iter().map(FieldValue::owned_any),
+                )))
+            })
+        },
+    )
+    .argument(InputValue::new(
+        "filter",
+        TypeRef::named_nn_list(FILTER_INPUT),
+    ))
+    .argument(InputValue::new("sort", TypeRef::named_nn_list(SORT_INPUT)))
+    .argument(InputValue::new("limit", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("offset", TypeRef::named(TypeRef::INT)));
+
+    let table = type_name.to_string();
+    let count = Field::new(
+        format!("{}Count", base),
+        TypeRef::named_nn(TypeRef::INT),
+        move |ctx| {
+            let table = table.clone();
+            FieldFuture::new(async move {
+                let query = list_query(&ctx)?;
+                let db = ctx.data::<Arc<SqliteManager>>()?;
+                let total = db.count_dynamic(&table, &query.filters)?;
+                Ok(Some(FieldValue::value(GqlValue::Number(total.into()))))
+            })
+        },
+    )
+    .argument(InputValue::new(
+        "filter",
+        TypeRef::named_nn_list(FILTER_INPUT),
+    ));
+
+    query.field(single).field(list).field(count)
+}
+
+fn list_query(ctx: &ResolverContext<'_>) -> async_graphql::Result<DataQuery> {
+    let filter = match ctx.args.get("filter") {
+        Some(v) => Some(v.deserialize::<Vec<FieldFilterInput>>()?),
+        None => None,
+    };
+    let sort = match ctx.args.get("sort") {
+        Some(v) => Some(v.deserialize::<Vec<SortInput>>()?),
+        None => None,
+    };
+    let limit = ctx.args.get("limit").map(|v| v.u64()).transpose()?;
+    let offset = ctx.args.get("offset").map(|v| v.u64()).transpose()?;
+    Ok(data_query(
+        filter,
+        sort,
+        limit.map(|l| l as u32),
+        offset.map(|o| o as u32),
+    ))
+}
+
+fn column(name: &str, kind: ColumnKind, non_null: bool) -> Field {
+    let key = name.to_string();
+    Field::new(name, kind.type_ref(non_null), move |ctx| {
+        FieldFuture::from_value(read_column(&ctx, &key, kind))
+    })
+}
+
+/// Polje čije se ime razlikuje od stupca (npr. `client_id` čita stupac `client`).
+fn column_as(name: &str, column_name: &str, kind: ColumnKind) -> Field {
+    let key = column_name.to_string();
+    Field::new(name, kind.type_ref(false), move |ctx| {
+        FieldFuture::from_value(read_column(&ctx, &key, kind))
+    })
+}
+
+fn read_column(ctx: &ResolverContext<'_>, key: &str, kind: ColumnKind) -> Option<GqlValue> {
+    let row = ctx.parent_value.downcast_ref::<JsonValue>()?;
+    kind.convert(row.get(key)?)
+}
+
+/// Relacija razriješena u puni objekt ciljanog Clouda.
+fn relation(field: &str, target: &str) -> Field {
+    let key = field.to_string();
+    let target_table = target.to_string();
+    Field::new(field, TypeRef::named(target), move |ctx| {
+        let key = key.clone();
+        let target_table = target_table.clone();
+        FieldFuture::new(async move {
+            let row = ctx.parent_value.try_downcast_ref::<JsonValue>()?;
+            let Some(id) = row.get(&key).and_then(JsonValue::as_str) else {
+                return Ok(None);
+            };
+            let db = ctx.data::<Arc<SqliteManager>>()?;
+            Ok(db.fetch_by_id(&target_table, id)?.map(FieldValue::owned_any))
+        })
+    })
+}
+
+/// Relacija `many`: lista punih objekata ciljanog Clouda, redom iz meta fajla.
+fn relation_list(island: &str, field: &str, target: &str) -> Field {
+    let table = island.to_string();
+    let key = field.to_string();
+    let target_table = target.to_string();
+    Field::new(field, TypeRef::named_nn_list_nn(target), move |ctx| {
+        let table = table.clone();
+        let key = key.clone();
+        let target_table = target_table.clone();
+        FieldFuture::new(async move {
+            let row = ctx.parent_value.try_downcast_ref::<JsonValue>()?;
+            let Some(id) = row.get("id").and_then(JsonValue::as_str) else {
+                return Ok(None);
+            };
+            let db = ctx.data::<Arc<SqliteManager>>()?;
+            let mut items = Vec::new();
+            for target_id in db.fetch_island_links(&table, id, &key)? {
+                items.extend(db.fetch_by_id(&target_table, &target_id)?);
+            }
+            Ok(Some(FieldValue::list(
+                items.into_iter().map(FieldValue::owned_any),
+            )))
+        })
+    })
+}
+
+/// `<field>_ids` za relaciju `many`.
+fn link_ids(island: &str, field: &str) -> Field {
+    let table = island.to_string();
+    let key = field.to_string();
+    Field::new(
+        format!("{}_ids", field),
+        TypeRef::named_nn_list_nn(TypeRef::ID),
+        move |ctx| {
+            let table = table.clone();
+            let key = key.clone();
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<JsonValue>()?;
+                let Some(id) = row.get("id").and_then(JsonValue::as_str) else {
+                    return Ok(None);
+                };
+                let db = ctx.data::<Arc<SqliteManager>>()?;
+                let ids = db.fetch_island_links(&table, id, &key)?;
+                Ok(Some(FieldValue::list(
+                    ids.into_iter().map(|i| FieldValue::value(GqlValue::String(i))),
+                )))
+            })
+        },
+    )
+}
+
+/// Tagovi otoka iz tablice `tags`.
+fn tag_names(island: &str) -> Field {
+    let table = island.to_string();
+    Field::new(
+        TAGS_KEY,
+        TypeRef::named_nn_list_nn(TypeRef::STRING),
+        move |ctx| {
+            let table = table.clone();
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<JsonValue>()?;
+                let Some(id) = row.get("id").and_then(JsonValue::as_str) else {
+                    return Ok(None);
+                };
+                let db = ctx.data::<Arc<SqliteManager>>()?;
+                let tags = db.fetch_island_tags(&table, id)?;
+                Ok(Some(FieldValue::list(
+                    tags.into_iter().map(|t| FieldValue::value(GqlValue::String(t))),
+                )))
+            })
+        },
+    )
+}
+
+/// Svi otoci tipa `island` čija relacija `field` pokazuje na roditeljski zapis.
+fn referrers(island: &str, field: &str, cardinality: Cardinality) -> Field {
+    let name = format!("{}ListBy{}", lower_first(island), upper_first(field));
+    let table = island.to_string();
+    let column = field.to_string();
+    Field::new(name, TypeRef::named_nn_list_nn(island), move |ctx| {
+        let table = table.clone();
+        let column = column.clone();
+        FieldFuture::new(async move {
+            let row = ctx.parent_value.try_downcast_ref::<JsonValue>()?;
+            let Some(id) = row.get("id").and_then(JsonValue::as_str) else {
+                return Ok(None);
+            };
+            let db = ctx.data::<Arc<SqliteManager>>()?;
+            let rows = db.fetch_referrers_by(&table, &column, cardinality, id)?;
+            Ok(Some(FieldValue::list(
+                rows.into_iter().map(FieldValue::owned_any),
+            )))
+        })
+    })
+}
+
+fn upper_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn lower_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) => c.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregator::AggregationValue;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    const CONFIG: &str = r#"
+GLOBAL:
+  company_name: "Test"
+  currency_symbol: "$"
+  locale: "en_US"
+CLOUDS:
+  - name: "Client"
+    icon: "briefcase"
+    fields:
+      - key: "name"
+        type: "string"
+        required: true
+      - key: "active"
+        type: "boolean"
+      - key: "status"
+        type: "select"
+        options: ["Active", "Lead"]
+        required: true
+ISLANDS:
+  - name: "Project"
+    root_path: "./projects/*"
+    meta_file: "meta.yaml"
+    relations:
+      - field: "client"
+        target_cloud: "Client"
+    aggregations:
+      - name: "total_tasks"
+        path: "*.md"
+        target_field: "id"
+        logic: "count"
+"#;
+
+    #[tokio::test]
+    async fn test_typed_schema_resolves_fields_and_relations() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let db = Arc::new(SqliteManager::new(":memory:").unwrap());
+        db.init_schema(&config).unwrap();
+
+        let data = json!({"name": "Acme", "active": true, "status": "Active"});
+        let client_id =
+            db.create_cloud_entity(&config, "Client", data.as_object().unwrap()).unwrap();
+        let mut relations = HashMap::new();
+        relations.insert("client".to_string(), Some(client_id.clone()));
+        let mut aggregations = HashMap::new();
+        aggregations.insert("total_tasks".to_string(), AggregationValue::Number(3.0));
+        db.upsert_island(
+            "Project",
+            "p1",
+            "Phoenix",
+            "/p",
+            None,
+            &[],
+            &relations,
+            &aggregations,
+        )
+        .unwrap();
+
+        let schema = build(&config, db).unwrap();
+        let sdl = schema.sdl();
+        assert!(sdl.contains("type Client"));
+        assert!(sdl.contains("active: Boolean"));
+        assert!(sdl.contains("projectList("));
+        assert!(sdl.contains("projectListByClient: [Project!]!"));
+
+        let res = schema
+            .execute(
+                r#"{ projectList(filter: [{field: "name", eq: "Phoenix"}]) {
+                    name total_tasks client_id client { name active }
+                } projectCount }"#,
+            )
+            .await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(
+            res.data.into_json().unwrap(),
+            json!({
+                "projectList": [{
+                    "name": "Phoenix",
+                    "total_tasks": 3.0,
+                    "client_id": client_id,
+                    "client": {"name": "Acme", "active": true}
+                }],
+                "projectCount": 1
+            })
+        );
+
+        let res = schema.execute("{ clientList { name projectListByClient { name } } }").await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(
+            res.data.into_json().unwrap(),
+            json!({"clientList": [{"name": "Acme", "projectListByClient": [{"name": "Phoenix"}]}]})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_approved_creation_with_empty_required_field_is_queryable() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let db = Arc::new(SqliteManager::new(":memory:").unwrap());
+        db.init_schema(&config).unwrap();
+
+        db.check_or_create_pending("Client", "name", "Globex", "{}").unwrap();
+        let action_id = db.fetch_pending_actions().unwrap()[0]["id"].as_str().unwrap().to_string();
+        db.approve_pending_creation(&action_id, &config, &serde_json::Map::new())
+            .unwrap();
+
+        let schema = build(&config, db).unwrap();
+        let res = schema.execute("{ clientList { name status } }").await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        assert_eq!(
+            res.data.into_json().unwrap(),
+            json!({"clientList": [{"name": "Globex", "status": null}]})
+        );
+    }
+}
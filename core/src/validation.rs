@@ -0,0 +1,503 @@
+// core/src/validation.rs
+
+//! Validacija `valter.config` datoteke.
+//!
+//! Za razliku od serde parsiranja koje staje na prvoj grešci, ovdje se skupljaju
+//! svi problemi odjednom i svakome se pridružuje linija/stupac iz YAML izvora.
+
+use crate::config::{AggregationLogic, CloudField, Config, EXTRA_COLUMN, STATUS_COLUMN};
+use crate::derived;
+use crate::field_path::FieldPath;
+use crate::filter::FilterExpr;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use yaml_rust2::parser::{Event, Parser};
+
+/// Stupci koje svaka Island tablica ima neovisno o konfiguraciji (uključujući
+/// `tags`, koji nije stupac, ali je polje svakog otoka u API-ju). Jedino se
+/// `status` smije deklarirati kao polje otoka.
+pub const RESERVED_ISLAND_COLUMNS: &[&str] = &[
+    "id",
+    "name",
+    "path",
+    "status",
+    "updated_at",
+    "parent_id",
+    "tags",
+];
+
+/// Interne tablice koje `SqliteManager::init_schema` stvara uz Cloud/Island tablice.
+pub const INTERNAL_TABLES: &[&str] = &[
+    "_valter_system",
+    "pending_actions",
+    "island_links",
+    "scan_errors",
+    "tags",
+    "island_tags",
+    "aggregation_cache",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Putanja unutar konfiguracije, npr. `CLOUDS[1].fields[0].key`.
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}:{} {}", self.line, self.column, self.message)
+        } else {
+            write!(
+                f,
+                "{}:{} [{}] {}",
+                self.line, self.column, self.path, self.message
+            )
+        }
+    }
+}
+
+/// Provjerava SQL identifikator (ime tablice ili stupca).
+pub fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Učitava datoteku i vraća sve dijagnostike (prazna lista znači da je konfiguracija ispravna).
+pub fn check_file(path: &Path) -> Result<Vec<Diagnostic>> {
+    let source = fs::read_to_string(path)?;
+    match serde_yaml::from_str::<Config>(&source) {
+        Ok(config) => Ok(validate(&config, &source)),
+        Err(e) => {
+            let (line, column) = e.location().map_or((0, 0), |l| (l.line(), l.column()));
+            Ok(vec![Diagnostic {
+                path: String::new(),
+                line,
+                column,
+                message: e.to_string(),
+            }])
+        }
+    }
+}
+
+/// Pokreće sve provjere nad već parsiranom konfiguracijom.
+pub fn validate(config: &Config, source: &str) -> Vec<Diagnostic> {
+    let mut v = Validator {
+        map: SourceMap::build(source),
+        diagnostics: Vec::new(),
+    };
+
+    if config.clouds.is_empty() {
+        v.report(
+            "CLOUDS",
+            "Configuration must define at least one CLOUD.".to_string(),
+        );
+    }
+
+    let mut table_names: HashMap<String, String> = HashMap::new();
+
+    for (i, cloud) in config.clouds.iter().enumerate() {
+        let base = format!("CLOUDS[{}]", i);
+        v.check_table_name(&mut table_names, &cloud.name, &base);
+
+        let mut keys = HashSet::new();
+        for (j, field) in cloud.fields.iter().enumerate() {
+            let path = format!("{}.fields[{}]", base, j);
+            v.check_identifier(&field.key, &format!("{}.key", path), "Field key");
+            if field.key == "id" {
+                v.report(
+                    &format!("{}.key", path),
+                    "Field key 'id' is reserved".to_string(),
+                );
+            }
+            if !keys.insert(field.key.as_str()) {
+                v.report(
+                    &format!("{}.key", path),
+                    format!(
+                        "Duplicate field key '{}' in cloud '{}'",
+                        field.key, cloud.name
+                    ),
+                );
+            }
+            v.check_field_options(field, &path);
+        }
+    }
+
+    for (i, island) in config.islands.iter().enumerate() {
+        let base = format!("ISLANDS[{}]", i);
+        v.check_table_name(&mut table_names, &island.name, &base);
+
+        if island.root_path.trim().is_empty() {
+            v.report(
+                &format!("{}.root_path", base),
+                "root_path must not be empty".to_string(),
+            );
+        } else if let Err(e) = island.root_pattern() {
+            v.report(&format!("{}.root_path", base), e.to_string());
+        }
+
+        let mut columns: HashSet<String> = HashSet::new();
+        if island.capture_extra {
+            columns.insert(EXTRA_COLUMN.to_string());
+        }
+        let mut check_column = |v: &mut Validator, name: &str, path: &str, what: &str| {
+            v.check_identifier(name, path, what);
+            // `status` se smije deklarirati samo kao polje, ne kao relacija ili metrika.
+            let declarable = what == "Field key" && name == STATUS_COLUMN;
+            if RESERVED_ISLAND_COLUMNS.contains(&name) && !declarable {
+                v.report(
+                    path,
+                    format!("{} '{}' collides with a built-in island column", what, name),
+                );
+            } else if !columns.insert(name.to_string()) {
+                v.report(
+                    path,
+                    format!("Duplicate column '{}' on island '{}'", name, island.name),
+                );
+            }
+        };
+
+        for (j, rel) in island.relations.iter().enumerate() {
+            let path = format!("{}.relations[{}]", base, j);
+            check_column(
+                &mut v,
+                &rel.field,
+                &format!("{}.field", path),
+                "Relation field",
+            );
+            if !config.clouds.iter().any(|c| c.name == rel.target_cloud) {
+                v.report(
+                    &format!("{}.target_cloud", path),
+                    format!(
+                        "Relation '{}' points at unknown cloud '{}'",
+                        rel.field, rel.target_cloud
+                    ),
+                );
+            }
+        }
+
+        for (j, field) in island.fields.iter().enumerate() {
+            let path = format!("{}.fields[{}]", base, j);
+            check_column(&mut v, &field.key, &format!("{}.key", path), "Field key");
+            v.check_field_options(field, &path);
+        }
+
+        for key in island.match_fields.keys() {
+            if let Err(e) = FieldPath::parse(key) {
+                v.report(&format!("{}.match.{}", base, key), e.to_string());
+            }
+        }
+        for (j, pattern) in island.match_path.iter().enumerate() {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                v.report(
+                    &format!("{}.match_path[{}]", base, j),
+                    format!("Invalid match_path '{}': {}", pattern, e),
+                );
+            }
+        }
+
+        for (j, agg) in island.aggregations.iter().enumerate() {
+            let path = format!("{}.aggregations[{}]", base, j);
+            check_column(
+                &mut v,
+                &agg.name,
+                &format!("{}.name", path),
+                "Aggregation name",
+            );
+
+            if let Err(e) = glob::Pattern::new(&agg.path) {
+                v.report(
+                    &format!("{}.path", path),
+                    format!("Invalid glob '{}': {}", agg.path, e),
+                );
+            }
+            if let Err(e) = FieldPath::parse(&agg.target_field) {
+                v.report(&format!("{}.target_field", path), e.to_string());
+            }
+            match &agg.date_field {
+                Some(date_field) => {
+                    if let Err(e) = FieldPath::parse(date_field) {
+                        v.report(&format!("{}.date_field", path), e.to_string());
+                    }
+                }
+                None if matches!(agg.logic, AggregationLogic::First | AggregationLogic::Last) => {
+                    v.report(
+                        &format!("{}.logic", path),
+                        format!(
+                            "Aggregation '{}' uses first/last logic but has no date_field",
+                            agg.name
+                        ),
+                    );
+                }
+                None => {}
+            }
+            if let Some(filter) = &agg.filter {
+                if let Err(e) = FilterExpr::parse(filter) {
+                    v.report(
+                        &format!("{}.filter", path),
+                        format!("Invalid filter on aggregation '{}': {}", agg.name, e),
+                    );
+                }
+            }
+        }
+
+        for (j, metric) in island.derived.iter().enumerate() {
+            let path = format!("{}.derived[{}]", base, j);
+            check_column(
+                &mut v,
+                &metric.name,
+                &format!("{}.name", path),
+                "Derived metric name",
+            );
+        }
+        let known: Vec<&str> = island.aggregations.iter().map(|a| a.name.as_str()).collect();
+        if let Err(e) = derived::evaluation_order(&island.derived, &known) {
+            v.report(&format!("{}.derived", base), e.to_string());
+        }
+    }
+
+    v.diagnostics.sort_by_key(|d| (d.line, d.column));
+    v.diagnostics
+}
+
+struct Validator {
+    map: SourceMap,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator {
+    fn report(&mut self, path: &str, message: String) {
+        let (line, column) = self.map.locate(path);
+        self.diagnostics.push(Diagnostic {
+            path: path.to_string(),
+            line,
+            column,
+            message,
+        });
+    }
+
+    fn check_field_options(&mut self, field: &CloudField, path: &str) {
+        let has_options = field.options.as_ref().is_some_and(|o| !o.is_empty());
+        if field.field_type.has_options() && !has_options {
+            self.report(
+                &format!("{}.type", path),
+                format!(
+                    "Select field '{}' must define at least one option",
+                    field.key
+                ),
+            );
+        } else if !field.field_type.has_options() && field.options.is_some() {
+            self.report(
+                &format!("{}.options", path),
+                format!(
+                    "Field '{}' defines options but is not a select/multi-select",
+                    field.key
+                ),
+            );
+        }
+    }
+
+    fn check_identifier(&mut self, name: &str, path: &str, what: &str) {
+        if !is_valid_identifier(name) {
+            self.report(
+                path,
+                format!(
+                    "{} '{}' is not a valid identifier (use letters, digits and '_')",
+                    what, name
+                ),
+            );
+        }
+    }
+
+    /// Clouds i Islands dijele isti prostor imena tablica. SQLite ne razlikuje
+    /// velika i mala slova u imenima tablica, pa ni provjera.
+    fn check_table_name(&mut self, seen: &mut HashMap<String, String>, name: &str, base: &str) {
+        let path = format!("{}.name", base);
+        self.check_identifier(name, &path, "Name");
+        let folded = name.to_lowercase();
+        if INTERNAL_TABLES.contains(&folded.as_str()) {
+            let message = format!("Name '{}' collides with an internal table", name);
+            self.report(&path, message);
+        } else if let Some(first) = seen.get(&folded) {
+            let message = format!("Duplicate name '{}' (already used by {})", name, first);
+            self.report(&path, message);
+        } else {
+            seen.insert(folded, base.to_string());
+        }
+    }
+}
+
+// ========================================================================= //
+// SOURCE MAP: putanja u konfiguraciji -> (linija, stupac)
+// ========================================================================= //
+
+enum Frame {
+    Map { path: String, key: Option<String> },
+    Seq { path: String, index: usize },
+}
+
+struct SourceMap {
+    locations: HashMap<String, (usize, usize)>,
+}
+
+impl SourceMap {
+    fn build(source: &str) -> Self {
+        let mut locations = HashMap::new();
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut parser = Parser::new_from_str(source);
+
+        while let Ok((event, marker)) = parser.next_token() {
+            let position = (marker.line(), marker.col() + 1);
+
+            // Putanja čvora koji ovaj event otvara (ako je vrijednost, a ne ključ mape).
+            let node_path = match stack.last_mut() {
+                Some(Frame::Map { path, key }) => match key.take() {
+                    Some(k) => Some(join(path, &k)),
+                    None => {
+                        if let Event::Scalar(k, ..) = &event {
+                            locations.insert(join(path, k), position);
+                            *key = Some(k.clone());
+                        }
+                        None
+                    }
+                },
+                Some(Frame::Seq { path, index }) => {
+                    let p = format!("{}[{}]", path, index);
+                    *index += 1;
+                    locations.entry(p.clone()).or_insert(position);
+                    Some(p)
+                }
+                None => Some(String::new()),
+            };
+
+            match event {
+                Event::MappingStart(..) => stack.push(Frame::Map {
+                    path: node_path.unwrap_or_default(),
+                    key: None,
+                }),
+                Event::SequenceStart(..) => stack.push(Frame::Seq {
+                    path: node_path.unwrap_or_default(),
+                    index: 0,
+                }),
+                Event::MappingEnd | Event::SequenceEnd => {
+                    stack.pop();
+                }
+                Event::StreamEnd => break,
+                _ => {}
+            }
+        }
+
+        Self { locations }
+    }
+
+    /// Najbliža poznata lokacija: točna putanja ili prvi pronađeni roditelj.
+    fn locate(&self, path: &str) -> (usize, usize) {
+        let mut current = path;
+        loop {
+            if let Some(loc) = self.locations.get(current) {
+                return *loc;
+            }
+            match current.rfind(['.', '[']) {
+                Some(i) => current = &current[..i],
+                None => return (0, 0),
+            }
+        }
+    }
+}
+
+fn join(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+// ============== UNIT TESTS ==============
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BROKEN: &str = r#"GLOBAL:
+  company_name: "Test"
+  currency_symbol: "$"
+  locale: "en_US"
+CLOUDS:
+  - name: "Client"
+    icon: "briefcase"
+    fields:
+      - key: "full name"
+        type: "string"
+      - key: "status"
+        type: "select"
+  - name: "Client"
+    icon: "x"
+    fields: []
+ISLANDS:
+  - name: "Project"
+    root_path: "./projects/*"
+    meta_file: "meta.yaml"
+    relations:
+      - field: "operator"
+        target_cloud: "Operator"
+    aggregations:
+      - name: "total"
+        path: "invoices/[*.yaml"
+        target_field: "amount"
+        logic: "sum"
+"#;
+
+    #[test]
+    fn test_validate_reports_all_problems_with_locations() {
+        let config: Config = serde_yaml::from_str(BROKEN).unwrap();
+        let diagnostics = validate(&config, BROKEN);
+        let summary: Vec<(usize, &str)> =
+            diagnostics.iter().map(|d| (d.line, d.path.as_str())).collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (9, "CLOUDS[0].fields[0].key"),
+                (12, "CLOUDS[0].fields[1].type"),
+                (13, "CLOUDS[1].name"),
+                (22, "ISLANDS[0].relations[0].target_cloud"),
+                (25, "ISLANDS[0].aggregations[0].path"),
+            ]
+        );
+        assert_eq!(diagnostics[0].column, 9);
+        assert!(diagnostics[3].message.contains("unknown cloud 'Operator'"));
+    }
+
+    #[test]
+    fn test_table_names_ignore_case_and_avoid_internal_tables() {
+        let names = |second: &str| {
+            let yaml = BROKEN.replace("- name: \"Client\"\n    icon: \"x\"", second);
+            let config: Config = serde_yaml::from_str(&yaml).unwrap();
+            validate(&config, &yaml)
+                .into_iter()
+                .filter(|d| d.path == "CLOUDS[1].name")
+                .map(|d| d.message)
+                .collect::<Vec<_>>()
+        };
+        assert!(names("- name: \"client\"\n    icon: \"x\"")[0].contains("Duplicate name"));
+        assert!(names("- name: \"Tags\"\n    icon: \"x\"")[0].contains("internal table"));
+        assert!(names("- name: \"Invoice\"\n    icon: \"x\"").is_empty());
+    }
+
+    #[test]
+    fn test_identifier_rules() {
+        assert!(is_valid_identifier("total_tasks"));
+        assert!(is_valid_identifier("_x1"));
+        assert!(!is_valid_identifier("1st"));
+        assert!(!is_valid_identifier("full name"));
+        assert!(!is_valid_identifier("x;DROP"));
+        assert!(!is_valid_identifier(""));
+    }
+}
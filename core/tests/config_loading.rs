@@ -62,3 +62,43 @@ ISLANDS: []
         .to_string()
         .contains("must define at least one CLOUD"));
 }
+
+#[test]
+fn test_load_config_invalid_filter() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let config_path = dir.path().join("valter.test.config");
+    let mut file = File::create(&config_path).expect("Failed to create test config file");
+
+    let yaml_content = r#"
+GLOBAL:
+  company_name: "Test Corp"
+  currency_symbol: "$"
+  locale: "en_US"
+CLOUDS:
+  - name: "Client"
+    icon: "briefcase"
+    fields:
+      - key: "name"
+        type: "string"
+ISLANDS:
+  - name: "Project"
+    root_path: "./projects/*"
+    meta_file: "meta.yaml"
+    aggregations:
+      - name: "unpaid_total"
+        path: "invoices/*.yaml"
+        target_field: "amount"
+        logic: "sum"
+        filter: "status == 'unpaid' &&"
+"#;
+
+    write!(file, "{}", yaml_content).expect("Failed to write to test config");
+
+    let config_result = Config::load(config_path.to_str().unwrap());
+
+    assert!(config_result.is_err());
+    assert!(config_result
+        .unwrap_err()
+        .to_string()
+        .contains("Invalid filter on aggregation 'unpaid_total'"));
+}
@@ -0,0 +1,694 @@
+use notify::event::{ModifyKind, RemoveKind, RenameMode};
+use notify::{Event, EventKind};
+use rusqlite::types::Value as SqlValue;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::tempdir;
+use valter_core::cloud::{DataFilter, DataQuery, FilterOp, SqliteManager};
+use valter_core::config::Config;
+use valter_core::events::ValterEvent;
+use valter_core::processor::EventProcessor;
+use valter_core::scan::{ScanJob, ScanJobs, ScanState};
+
+fn setup(root: &Path, islands: &str) -> (Arc<SqliteManager>, Arc<Config>) {
+    let yaml = format!(
+        r#"
+GLOBAL:
+  company_name: "Test Corp"
+  currency_symbol: "$"
+  locale: "en_US"
+CLOUDS:
+  - name: "Operator"
+    icon: "user"
+    fields:
+      - key: "name"
+        type: "string"
+        required: true
+ISLANDS:
+{}
+"#,
+        islands.replace("{root}", &root.to_string_lossy())
+    );
+    let config: Arc<Config> = Arc::new(serde_yaml::from_str(&yaml).unwrap());
+    let db = Arc::new(SqliteManager::new(":memory:").unwrap());
+    db.init_schema(&config).unwrap();
+    (db, config)
+}
+
+#[test]
+fn test_many_relation_links_each_element() {
+    let dir = tempdir().unwrap();
+    island_dir(
+        dir.path(),
+        "phoenix",
+        "name: Phoenix\nteam: [Arnold S., Sarah C.]\n",
+    );
+
+    let (db, config) = setup(
+        dir.path(),
+        r#"
+  - name: "Project"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+    relations:
+      - field: "team"
+        target_cloud: "Operator"
+        cardinality: "many"
+"#,
+    );
+    let arnold = db
+        .create_cloud_entity(
+            &config,
+            "Operator",
+            serde_json::json!({"name": "Arnold S."}).as_object().unwrap(),
+        )
+        .unwrap();
+
+    let processor = EventProcessor::new(db.clone(), config.clone());
+    processor.scan_on_startup();
+
+    // "Sarah C." ne postoji, pa čeka odobrenje; Arnold je odmah povezan.
+    let pending = db.fetch_pending_actions().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0]["value"], "Sarah C.");
+
+    let mut rows = db.fetch_all_dynamic("Project").unwrap();
+    db.attach_island_links(&config, "Project", &mut rows).unwrap();
+    assert_eq!(rows[0]["team"], serde_json::json!([arnold]));
+
+    let action_id = pending[0]["id"].as_str().unwrap();
+    let sarah = db
+        .approve_pending_creation(action_id, &config, &serde_json::Map::new())
+        .unwrap();
+    processor.scan_on_startup();
+
+    let mut rows = db.fetch_all_dynamic("Project").unwrap();
+    db.attach_island_links(&config, "Project", &mut rows).unwrap();
+    assert_eq!(rows[0]["team"], serde_json::json!([arnold, sarah]));
+    db.expand_relations(&config, "Project", &mut rows).unwrap();
+    assert_eq!(rows[0]["team"][1]["name"], "Sarah C.");
+
+    let referrers = db.fetch_referrers(&config, "Operator", &sarah).unwrap();
+    assert_eq!(referrers["Project"][0]["name"], "Phoenix");
+    assert!(db.delete_cloud_entity(&config, "Operator", &sarah).is_err());
+}
+
+const PROJECTS: &str = r#"
+  - name: "Project"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+"#;
+
+/// Folder otoka `root/sub` s meta fajlom zadanog sadržaja.
+fn island_dir(root: &Path, sub: &str, meta: &str) -> PathBuf {
+    let dir = root.join(sub);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("meta.yaml"), meta).unwrap();
+    dir
+}
+
+fn meta_id(dir: &Path) -> String {
+    let yaml: serde_yaml::Value =
+        serde_yaml::from_str(&fs::read_to_string(dir.join("meta.yaml")).unwrap()).unwrap();
+    yaml["valter_id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_island_identity_survives_rename_and_duplicate_names() {
+    let dir = tempdir().unwrap();
+    // Isto ime u dva foldera više ne smije pregaziti jedan drugog.
+    let alpha = island_dir(dir.path(), "alpha", "name: Phoenix\n");
+    let beta = island_dir(dir.path(), "beta", "name: Phoenix\n");
+
+    let (db, config) = setup(dir.path(), PROJECTS);
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+
+    let alpha_id = meta_id(&alpha);
+    assert_ne!(alpha_id, meta_id(&beta));
+    assert_eq!(db.fetch_all_dynamic("Project").unwrap().len(), 2);
+
+    fs::write(
+        alpha.join("meta.yaml"),
+        format!("name: Phoenix Reborn\nvalter_id: {}\n", alpha_id),
+    )
+    .unwrap();
+    processor.scan_on_startup();
+
+    let row = db.fetch_by_id("Project", &alpha_id).unwrap().unwrap();
+    assert_eq!(row["name"], "Phoenix Reborn");
+    assert_eq!(db.fetch_all_dynamic("Project").unwrap().len(), 2);
+
+    // Kopija foldera nosi isti `valter_id`, ali dobiva vlastiti.
+    let copy = dir.path().join("alpha-copy");
+    fs::create_dir_all(&copy).unwrap();
+    fs::copy(alpha.join("meta.yaml"), copy.join("meta.yaml")).unwrap();
+    processor.scan_on_startup();
+
+    assert_eq!(meta_id(&alpha), alpha_id);
+    assert_ne!(meta_id(&copy), alpha_id);
+    assert_eq!(db.fetch_all_dynamic("Project").unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_stale_islands_are_removed_or_archived() {
+    let dir = tempdir().unwrap();
+    let alpha = island_dir(dir.path(), "alpha", "name: Alpha\n");
+    let beta = island_dir(dir.path(), "beta", "name: Beta\n");
+
+    let (db, config) = setup(dir.path(), PROJECTS);
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+    let beta_id = meta_id(&beta);
+
+    // Premještanje foldera zadržava isti redak, samo s novom putanjom.
+    let moved = dir.path().join("beta-moved");
+    fs::rename(&beta, &moved).unwrap();
+    let rename = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+        .add_path(beta.clone())
+        .add_path(moved.clone());
+    processor.handle_event(rename).await;
+    let row = db.fetch_by_id("Project", &beta_id).unwrap().unwrap();
+    assert_eq!(row["path"], moved.to_string_lossy().as_ref());
+
+    fs::remove_dir_all(&alpha).unwrap();
+    // Nestali fajl koji nije ni otok ni meta fajl ne pokreće usklađivanje.
+    let swap = Event::new(EventKind::Remove(RemoveKind::File))
+        .add_path(moved.join(".meta.yaml.swp"))
+        .add_path(moved.join("meta.tmp"));
+    processor.handle_event(swap).await;
+    assert_eq!(db.fetch_all_dynamic("Project").unwrap().len(), 2);
+
+    let remove = Event::new(EventKind::Remove(RemoveKind::Folder)).add_path(alpha.clone());
+    processor.handle_event(remove).await;
+    let rows = db.fetch_all_dynamic("Project").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["name"], "Beta");
+
+    // `on_missing: archive` zadržava redak i vraća ga kad se folder vrati.
+    let (db, config) = setup(
+        dir.path(),
+        &format!("{}    on_missing: \"archive\"\n", PROJECTS),
+    );
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+    let outside = tempdir().unwrap();
+    let backup = outside.path().join("beta");
+    fs::rename(&moved, &backup).unwrap();
+    processor.scan_on_startup();
+    let row = db.fetch_by_id("Project", &beta_id).unwrap().unwrap();
+    assert_eq!(row["status"], "Missing");
+
+    fs::rename(&backup, &moved).unwrap();
+    processor.scan_on_startup();
+    let row = db.fetch_by_id("Project", &beta_id).unwrap().unwrap();
+    assert!(row["status"].is_null());
+}
+
+#[test]
+fn test_only_folders_matching_root_path_become_islands() {
+    let dir = tempdir().unwrap();
+    for sub in [
+        "acme/projects/phoenix",
+        "acme/projects/phoenix/docs",
+        "acme/archive/old",
+    ] {
+        island_dir(dir.path(), sub, &format!("name: {}\n", sub));
+    }
+
+    let (db, config) = setup(
+        dir.path(),
+        r#"
+  - name: "Project"
+    root_path: "{root}/*/projects/*"
+    meta_file: "meta.yaml"
+"#,
+    );
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+
+    // Ugniježđeni meta.yaml i folderi izvan uzorka se ignoriraju.
+    let rows = db.fetch_all_dynamic("Project").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["name"], "acme/projects/phoenix");
+    let expected = fs::canonicalize(dir.path().join("acme/projects/phoenix")).unwrap();
+    assert_eq!(rows[0]["path"], expected.to_string_lossy().as_ref());
+}
+
+#[test]
+fn test_match_rules_assign_each_meta_file_to_one_island_type() {
+    let dir = tempdir().unwrap();
+    for (sub, meta) in [
+        ("phoenix", "name: Phoenix\ntype: project\n"),
+        ("acme", "name: Acme\ntype: client\n"),
+        ("notes", "name: Notes\n"),
+        ("clients/globex", "name: Globex\n"),
+    ] {
+        island_dir(dir.path(), sub, meta);
+    }
+
+    let (db, config) = setup(
+        dir.path(),
+        r#"
+  - name: "Project"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+    match:
+      type: project
+  - name: "Client"
+    root_path: "{root}/{*,clients/*}"
+    meta_file: "meta.yaml"
+    match:
+      type: [client, customer]
+  - name: "ClientFolder"
+    root_path: "{root}/**"
+    meta_file: "meta.yaml"
+    match_path: ["**/clients/*"]
+  - name: "Misc"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+"#,
+    );
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+
+    let names = |table: &str| -> Vec<String> {
+        db.fetch_all_dynamic(table)
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(names("Project"), vec!["Phoenix"]);
+    assert_eq!(names("Client"), vec!["Acme"]);
+    assert_eq!(names("ClientFolder"), vec!["Globex"]);
+    // Bez `type` polja otok pada na definiciju bez pravila.
+    assert_eq!(names("Misc"), vec!["Notes"]);
+}
+
+#[tokio::test]
+async fn test_nested_islands_link_to_parent_and_skip_child_files() {
+    let dir = tempdir().unwrap();
+    let phoenix = island_dir(dir.path(), "phoenix", "name: Phoenix\n");
+    let wing = island_dir(dir.path(), "phoenix/wing", "name: Wing\n");
+    fs::create_dir_all(wing.join("invoices")).unwrap();
+    fs::create_dir_all(phoenix.join("invoices")).unwrap();
+    fs::write(phoenix.join("invoices/a.yaml"), "amount: 100\n").unwrap();
+    fs::write(wing.join("invoices/b.yaml"), "amount: 40\n").unwrap();
+
+    let (db, config) = setup(
+        dir.path(),
+        r#"
+  - name: "Project"
+    root_path: "{root}/**"
+    meta_file: "meta.yaml"
+    aggregations:
+      - name: "own_total"
+        path: "**/invoices/*.yaml"
+        target_field: "amount"
+        logic: "sum"
+      - name: "grand_total"
+        path: "**/invoices/*.yaml"
+        target_field: "amount"
+        logic: "sum"
+        include_children: true
+"#,
+    );
+    let processor = EventProcessor::new(db.clone(), config.clone());
+    processor.scan_on_startup();
+
+    let phoenix_id = meta_id(&phoenix);
+    let parent = db.fetch_by_id("Project", &phoenix_id).unwrap().unwrap();
+    assert_eq!(parent["own_total"], 100.0);
+    assert_eq!(parent["grand_total"], 140.0);
+    assert!(parent["parent_id"].is_null());
+    let child = db.fetch_by_id("Project", &meta_id(&wing)).unwrap().unwrap();
+    assert_eq!(child["parent_id"], phoenix_id.as_str());
+    assert_eq!(child["own_total"], 40.0);
+
+    // Promjena računa u djetetu osvježava i roditelja.
+    fs::write(wing.join("invoices/b.yaml"), "amount: 60\n").unwrap();
+    let modify =
+        Event::new(EventKind::Modify(ModifyKind::Any)).add_path(wing.join("invoices/b.yaml"));
+    processor.handle_event(modify).await;
+    let parent = db.fetch_by_id("Project", &phoenix_id).unwrap().unwrap();
+    assert_eq!(parent["grand_total"], 160.0);
+
+    let tree = db.fetch_island_tree(&config, Some("Project")).unwrap();
+    assert_eq!(tree.len(), 1);
+    assert_eq!(tree[0]["name"], "Phoenix");
+    assert_eq!(tree[0]["children"][0]["name"], "Wing");
+}
+
+#[test]
+fn test_non_island_sub_folders_count_in_parent() {
+    let dir = tempdir().unwrap();
+    let phoenix = island_dir(dir.path(), "phoenix", "name: Phoenix\n");
+    let wing = island_dir(dir.path(), "phoenix/wing", "name: Wing\n");
+    fs::create_dir_all(wing.join("invoices")).unwrap();
+    fs::create_dir_all(phoenix.join("invoices")).unwrap();
+    fs::write(phoenix.join("invoices/a.yaml"), "amount: 100\n").unwrap();
+    fs::write(wing.join("invoices/b.yaml"), "amount: 40\n").unwrap();
+
+    // `*` obuhvaća samo Phoenix; Wing nije otok, pa njegovi računi pripadaju roditelju.
+    let (db, config) = setup(
+        dir.path(),
+        r#"
+  - name: "Project"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+    aggregations:
+      - name: "own_total"
+        path: "**/invoices/*.yaml"
+        target_field: "amount"
+        logic: "sum"
+      - name: "grand_total"
+        path: "**/invoices/*.yaml"
+        target_field: "amount"
+        logic: "sum"
+        include_children: true
+"#,
+    );
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+
+    let rows = db.fetch_all_dynamic("Project").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["own_total"], 140.0);
+    assert_eq!(rows[0]["grand_total"], 140.0);
+}
+
+#[tokio::test]
+async fn test_batched_events_process_each_island_once() {
+    let dir = tempdir().unwrap();
+    let alpha = island_dir(dir.path(), "alpha", "name: Alpha\n");
+    let beta = island_dir(dir.path(), "beta", "name: Beta\n");
+    for folder in [&alpha, &beta] {
+        fs::create_dir_all(folder.join("docs")).unwrap();
+    }
+
+    let (db, config) = setup(dir.path(), PROJECTS);
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+    let mut rx = db.events().subscribe();
+
+    // Spremanje u editoru: više događaja za isti otok unutar jednog prozora.
+    let mut events = Vec::new();
+    for i in 0..5 {
+        let note = alpha.join("docs").join(format!("note{}.md", i));
+        fs::write(&note, "draft").unwrap();
+        events.push(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(note));
+    }
+    events.push(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(alpha.join("meta.yaml")));
+    events.push(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(beta.join("meta.yaml")));
+    processor.handle_events(events).await;
+
+    let mut upserted = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let ValterEvent::IslandUpserted { name, .. } = event {
+            upserted.push(name);
+        }
+    }
+    upserted.sort();
+    assert_eq!(upserted, vec!["Alpha", "Beta"]);
+}
+
+#[tokio::test]
+async fn test_background_scan_reports_progress() {
+    let dir = tempdir().unwrap();
+    for i in 0..12 {
+        let meta = if i == 3 {
+            "name: [broken\n".to_string()
+        } else {
+            format!("name: P{}\n", i)
+        };
+        island_dir(dir.path(), &format!("p{:02}", i), &meta);
+    }
+
+    let (db, config) = setup(dir.path(), PROJECTS);
+    let processor = Arc::new(EventProcessor::new(db.clone(), config));
+    let scans = ScanJobs::default();
+    let job = scans.start(processor.clone());
+    // Dok posao traje, novi zahtjev vraća isti posao.
+    let again = scans.start(processor.clone());
+    if !job.is_finished() {
+        assert_eq!(again.id(), job.id());
+    }
+
+    while !job.is_finished() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let progress = scans.get(job.id()).unwrap().progress();
+    assert_eq!(progress.state, ScanState::Completed);
+    assert_eq!(
+        (progress.found, progress.processed, progress.failed),
+        (12, 12, 1)
+    );
+    assert_eq!(db.fetch_all_dynamic("Project").unwrap().len(), 11);
+
+    let cancelled = ScanJob::new();
+    cancelled.cancel();
+    processor.scan(&cancelled);
+    assert_eq!(cancelled.progress().processed, 0);
+}
+
+#[tokio::test]
+async fn test_scan_errors_are_recorded_and_cleared() {
+    let dir = tempdir().unwrap();
+    island_dir(dir.path(), "good", "name: Good\n");
+    let bad = island_dir(dir.path(), "bad", "name: Bad\nteam: [one, two\n");
+
+    let (db, config) = setup(dir.path(), PROJECTS);
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+
+    let errors = db.fetch_scan_errors(None).unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["island_type"], "Project");
+    assert_eq!(errors[0]["kind"], "yaml");
+    assert!(errors[0]["line"].as_i64().unwrap() >= 2);
+    assert!(errors[0]["path"].as_str().unwrap().ends_with("bad/meta.yaml"));
+    let first_seen = errors[0]["first_seen"].clone();
+
+    processor.scan_on_startup();
+    let errors = db.fetch_scan_errors(Some("Project")).unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["first_seen"], first_seen);
+
+    fs::write(bad.join("meta.yaml"), "name: Bad\nteam: [one, two]\n").unwrap();
+    let fix = Event::new(EventKind::Modify(ModifyKind::Any)).add_path(bad.join("meta.yaml"));
+    processor.handle_event(fix).await;
+    assert!(db.fetch_scan_errors(None).unwrap().is_empty());
+
+    // Obrisani neispravni fajl također nestaje s popisa.
+    fs::write(bad.join("meta.yaml"), "name: [\n").unwrap();
+    processor.scan_on_startup();
+    assert_eq!(db.fetch_scan_errors(None).unwrap().len(), 1);
+    fs::remove_dir_all(&bad).unwrap();
+    let remove = Event::new(EventKind::Remove(RemoveKind::Folder)).add_path(bad.clone());
+    processor.handle_event(remove).await;
+    assert!(db.fetch_scan_errors(None).unwrap().is_empty());
+}
+
+#[test]
+fn test_broken_meta_under_match_rules_keeps_row() {
+    let dir = tempdir().unwrap();
+    let acme = island_dir(dir.path(), "acme", "name: Acme\ntype: client\n");
+    let (db, config) = setup(
+        dir.path(),
+        r#"
+  - name: "Project"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+    match:
+      type: "project"
+  - name: "Account"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+    match:
+      type: "client"
+"#,
+    );
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+    assert_eq!(db.fetch_all_dynamic("Account").unwrap().len(), 1);
+
+    // Tip se više ne može odrediti iz sadržaja, ali redak ostaje, a greška je vidljiva.
+    fs::write(acme.join("meta.yaml"), "name: Acme\ntype: [client\n").unwrap();
+    processor.scan_on_startup();
+    let rows = db.fetch_all_dynamic("Account").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["name"], "Acme");
+    assert!(db.fetch_all_dynamic("Project").unwrap().is_empty());
+    let errors = db.fetch_scan_errors(None).unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["island_type"], "Account");
+    assert_eq!(errors[0]["kind"], "yaml");
+}
+
+#[test]
+fn test_meta_fields_are_typed_and_stored() {
+    let dir = tempdir().unwrap();
+    let project = island_dir(
+        dir.path(),
+        "phoenix",
+        "name: Phoenix\nstatus: active\nbudget: \"1,250.5\"\ncreated_at: 2024-03-01\n\
+         priority: High\nowner: Sarah\nlabels: [a, b]\n",
+    );
+
+    let (db, config) = setup(
+        dir.path(),
+        r#"
+  - name: "Project"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+    capture_extra: true
+    fields:
+      - key: "budget"
+        type: "currency"
+      - key: "created_at"
+        type: "date"
+        required: true
+      - key: "priority"
+        type: "select"
+        options: ["High", "Low"]
+      - key: "description"
+        type: "string"
+"#,
+    );
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+
+    let rows = db.fetch_all_dynamic("Project").unwrap();
+    let row = &rows[0];
+    assert_eq!(row["status"], "active");
+    assert_eq!(row["budget"], 1250.5);
+    assert_eq!(row["created_at"], "2024-03-01");
+    assert_eq!(row["priority"], "High");
+    assert!(row["description"].is_null());
+    let extra: serde_json::Value = serde_json::from_str(row["extra"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        extra,
+        serde_json::json!({"owner": "Sarah", "labels": ["a", "b"]})
+    );
+
+    // Neispravna polja postaju NULL i završavaju u `scan_errors`, a ostatak
+    // otoka se i dalje ažurira.
+    fs::write(
+        project.join("meta.yaml"),
+        format!(
+            "name: Phoenix II\nvalter_id: {}\nbudget: 10\ncreated_at: soon\npriority: Urgent\n",
+            meta_id(&project)
+        ),
+    )
+    .unwrap();
+    processor.scan_on_startup();
+    let row = &db.fetch_all_dynamic("Project").unwrap()[0];
+    assert_eq!(row["name"], "Phoenix II");
+    assert_eq!(row["budget"], 10.0);
+    assert!(row["priority"].is_null() && row["created_at"].is_null());
+    let errors = db.fetch_scan_errors(None).unwrap();
+    assert_eq!(errors[0]["kind"], "validation");
+    let message = errors[0]["message"].as_str().unwrap();
+    assert!(message.contains("created_at") && message.contains("priority"));
+}
+
+#[test]
+fn test_declared_status_is_validated_and_replaces_missing() {
+    let dir = tempdir().unwrap();
+    let project = island_dir(dir.path(), "phoenix", "name: Phoenix\nstatus: Bogus\n");
+
+    let (db, config) = setup(
+        dir.path(),
+        r#"
+  - name: "Project"
+    root_path: "{root}/*"
+    meta_file: "meta.yaml"
+    on_missing: "archive"
+    fields:
+      - key: "status"
+        type: "select"
+        options: ["Active", "Done"]
+"#,
+    );
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+    let id = meta_id(&project);
+    let row = db.fetch_by_id("Project", &id).unwrap().unwrap();
+    assert!(row["status"].is_null());
+    let errors = db.fetch_scan_errors(None).unwrap();
+    assert!(errors[0]["message"].as_str().unwrap().contains("status"));
+
+    // `Missing` vrijedi dok folder ne postoji; vraćeni otok dobiva status iz meta fajla.
+    let outside = tempdir().unwrap();
+    let backup = outside.path().join("phoenix");
+    fs::rename(&project, &backup).unwrap();
+    processor.scan_on_startup();
+    let row = db.fetch_by_id("Project", &id).unwrap().unwrap();
+    assert_eq!(row["status"], "Missing");
+
+    fs::rename(&backup, &project).unwrap();
+    fs::write(
+        project.join("meta.yaml"),
+        format!("name: Phoenix\nvalter_id: {}\nstatus: Done\n", id),
+    )
+    .unwrap();
+    processor.scan_on_startup();
+    let row = db.fetch_by_id("Project", &id).unwrap().unwrap();
+    assert_eq!(row["status"], "Done");
+    assert!(db.fetch_scan_errors(None).unwrap().is_empty());
+}
+
+#[test]
+fn test_tags_are_normalised_and_counted() {
+    let dir = tempdir().unwrap();
+    for (sub, tags) in [
+        ("phoenix", "tags: [Rust, cli, rust, ' ']"),
+        ("nova", "tags: \"Rust, web\""),
+        ("legacy", "tags: [php]"),
+    ] {
+        island_dir(dir.path(), sub, &format!("name: {}\n{}\n", sub, tags));
+    }
+
+    let (db, config) = setup(dir.path(), PROJECTS);
+    let processor = EventProcessor::new(db.clone(), config);
+    processor.scan_on_startup();
+
+    let counts = db.fetch_tag_counts(None).unwrap();
+    assert_eq!(
+        counts,
+        vec![
+            serde_json::json!({"name": "Rust", "count": 2}),
+            serde_json::json!({"name": "cli", "count": 1}),
+            serde_json::json!({"name": "php", "count": 1}),
+            serde_json::json!({"name": "web", "count": 1}),
+        ]
+    );
+
+    let rust = DataQuery {
+        filters: vec![DataFilter {
+            field: "tags".to_string(),
+            op: FilterOp::HasTag,
+            value: SqlValue::Text("RUST".to_string()),
+        }],
+        ..DataQuery::default()
+    };
+    let mut rows = db.fetch_dynamic("Project", &rust).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(db.count_dynamic("Project", &rust.filters).unwrap(), 2);
+    db.attach_island_tags("Project", &mut rows).unwrap();
+    let nova = rows.iter().find(|r| r["name"] == "nova").unwrap();
+    assert_eq!(nova["tags"], serde_json::json!(["Rust", "web"]));
+
+    // Uklonjen zadnji `php` otok briše i sam tag.
+    fs::remove_dir_all(dir.path().join("legacy")).unwrap();
+    processor.scan_on_startup();
+    let names: Vec<_> = db
+        .fetch_tag_counts(Some("Project"))
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["Rust", "cli", "web"]);
+}